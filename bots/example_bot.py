@@ -0,0 +1,35 @@
+#!/usr/bin/env python3
+"""Example bot for the external bot protocol: heads for the closest fruit, avoiding walls and tails.
+
+Each tick the game writes the state as a single line of JSON to stdin, the bot answers with a line
+containing UP, DOWN, LEFT or RIGHT. Run it with: rusty_snake --controller "exec:python3 bots/example_bot.py"
+"""
+import json
+import sys
+
+MOVES = {"UP": (0, -1), "DOWN": (0, 1), "LEFT": (-1, 0), "RIGHT": (1, 0)}
+
+
+def is_free(state, x, y):
+    inset = state["wall_inset"]
+    if x <= inset or y <= inset or x >= state["width"] - 1 - inset or y >= state["height"] - 1 - inset:
+        return state["wall_wrap"]
+    return all([x, y] != part for snake in state["snakes"] if snake["alive"] for part in snake["tail"])
+
+
+def choose(state):
+    me = state["snakes"][state["you"]]
+    x, y = me["head"]
+    fruits = state["fruits"] or [[x, y]]
+    best = None
+    for name, (dx, dy) in MOVES.items():
+        if not is_free(state, x + dx, y + dy):
+            continue
+        distance = min(abs(x + dx - fx) + abs(y + dy - fy) for fx, fy in fruits)
+        if best is None or distance < best[0]:
+            best = (distance, name)
+    return best[1] if best else "UP"
+
+
+for line in sys.stdin:
+    print(choose(json.loads(line)), flush=True)
@@ -0,0 +1,125 @@
+;; Example bot for the WebAssembly bot interface: heads for the first fruit without turning back on itself.
+;;
+;; The game calls snake_alloc with the length of the observation, writes the observation as a line of JSON to the
+;; returned pointer and calls snake_next_direction, which answers 0 for UP, 1 for DOWN, 2 for LEFT or 3 for RIGHT.
+;; Compile it with wat2wasm from the WebAssembly Binary Toolkit and run it with:
+;;   wat2wasm bots/example_bot.wat -o bots/example_bot.wasm
+;;   rusty_snake --controller wasm:bots/example_bot.wasm
+(module
+  (memory (export "memory") 1)
+
+  ;; keys searched for in the observation
+  (data (i32.const 0) "\"you\":")
+  (data (i32.const 16) "\"head\":[")
+  (data (i32.const 32) "\"fruits\":[[")
+  (data (i32.const 48) "\"direction\":\"")
+
+  ;; the observation is written behind the keys
+  (global $buffer i32 (i32.const 1024))
+  ;; position behind the last parsed number
+  (global $pos (mut i32) (i32.const 0))
+
+  ;; grows the memory until the observation fits into the buffer
+  (func (export "snake_alloc") (param $len i32) (result i32)
+    (local $missing_pages i32)
+    (local.set $missing_pages
+      (i32.sub
+        (i32.shr_u (i32.add (i32.add (global.get $buffer) (local.get $len)) (i32.const 65535)) (i32.const 16))
+        (memory.size)))
+    (if (i32.gt_s (local.get $missing_pages) (i32.const 0))
+      (then (drop (memory.grow (local.get $missing_pages)))))
+    (global.get $buffer))
+
+  ;; returns the position right behind the first occurrence of the key between from and end, -1 if there is none
+  (func $find (param $key i32) (param $key_len i32) (param $from i32) (param $end i32) (result i32)
+    (local $i i32)
+    (local $j i32)
+    (local.set $i (local.get $from))
+    (block $not_found
+      (loop $candidates
+        (br_if $not_found (i32.gt_s (i32.add (local.get $i) (local.get $key_len)) (local.get $end)))
+        (local.set $j (i32.const 0))
+        (block $mismatch
+          (loop $chars
+            (if (i32.eq (local.get $j) (local.get $key_len))
+              (then (return (i32.add (local.get $i) (local.get $j)))))
+            (br_if $mismatch
+              (i32.ne
+                (i32.load8_u (i32.add (local.get $i) (local.get $j)))
+                (i32.load8_u (i32.add (local.get $key) (local.get $j)))))
+            (local.set $j (i32.add (local.get $j) (i32.const 1)))
+            (br $chars)))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $candidates)))
+    (i32.const -1))
+
+  ;; parses the number starting at the position and sets $pos behind it
+  (func $parse_number (param $at i32) (result i32)
+    (local $value i32)
+    (local $digit i32)
+    (global.set $pos (local.get $at))
+    (block $done
+      (loop $digits
+        (local.set $digit (i32.sub (i32.load8_u (global.get $pos)) (i32.const 48)))
+        (br_if $done (i32.gt_u (local.get $digit) (i32.const 9)))
+        (local.set $value (i32.add (i32.mul (local.get $value) (i32.const 10)) (local.get $digit)))
+        (global.set $pos (i32.add (global.get $pos) (i32.const 1)))
+        (br $digits)))
+    (local.get $value))
+
+  (func (export "snake_next_direction") (param $ptr i32) (param $len i32) (result i32)
+    (local $end i32)
+    (local $snakes_left i32)
+    (local $at i32)
+    (local $x i32)
+    (local $y i32)
+    (local $fruit_x i32)
+    (local $fruit_y i32)
+    (local $heading_char i32)
+    (local $heading i32)
+    (local $move i32)
+    (local.set $end (i32.add (local.get $ptr) (local.get $len)))
+
+    ;; the head of our snake is the head following the one of every snake before it
+    (local.set $snakes_left (call $parse_number (call $find (i32.const 0) (i32.const 6) (local.get $ptr) (local.get $end))))
+    (local.set $at (local.get $ptr))
+    (loop $snakes
+      (local.set $at (call $find (i32.const 16) (i32.const 8) (local.get $at) (local.get $end)))
+      (local.set $snakes_left (i32.sub (local.get $snakes_left) (i32.const 1)))
+      (br_if $snakes (i32.ge_s (local.get $snakes_left) (i32.const 0))))
+    (local.set $x (call $parse_number (local.get $at)))
+    (local.set $y (call $parse_number (i32.add (global.get $pos) (i32.const 1))))
+
+    ;; the current heading as a direction code, -1 while standing still
+    (local.set $heading_char
+      (i32.load8_u (call $find (i32.const 48) (i32.const 13) (global.get $pos) (local.get $end))))
+    (local.set $heading
+      (select (i32.const 0)
+        (select (i32.const 1)
+          (select (i32.const 2)
+            (select (i32.const 3) (i32.const -1) (i32.eq (local.get $heading_char) (i32.const 82)))
+            (i32.eq (local.get $heading_char) (i32.const 76)))
+          (i32.eq (local.get $heading_char) (i32.const 68)))
+        (i32.eq (local.get $heading_char) (i32.const 85))))
+
+    ;; keep the heading if there is no fruit
+    (local.set $at (call $find (i32.const 32) (i32.const 11) (local.get $ptr) (local.get $end)))
+    (if (i32.lt_s (local.get $at) (i32.const 0))
+      (then (return (select (local.get $heading) (i32.const 0) (i32.ge_s (local.get $heading) (i32.const 0))))))
+    (local.set $fruit_x (call $parse_number (local.get $at)))
+    (local.set $fruit_y (call $parse_number (i32.add (global.get $pos) (i32.const 1))))
+
+    (local.set $move
+      (if (result i32) (i32.lt_s (local.get $fruit_x) (local.get $x))
+        (then (i32.const 2))
+        (else
+          (if (result i32) (i32.gt_s (local.get $fruit_x) (local.get $x))
+            (then (i32.const 3))
+            (else (select (i32.const 0) (i32.const 1) (i32.lt_s (local.get $fruit_y) (local.get $y))))))))
+
+    ;; turning back would bite the tail, turn sideways instead: UP and DOWN differ in the lowest bit, UP and LEFT in
+    ;; the second lowest
+    (if (i32.eq (local.get $heading) (i32.xor (local.get $move) (i32.const 1)))
+      (then (local.set $move (i32.xor (local.get $move) (i32.const 2)))))
+    (local.get $move))
+)
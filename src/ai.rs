@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+
+use crate::{FIELD_HEIGHT, FIELD_WIDTH};
+use crate::direction::Direction;
+use crate::game_mode::GameMode;
+use crate::game_state::GameState;
+use crate::ruleset::Ruleset;
+
+/// Maximum number of fields counted when comparing the space left after a move in tron mode
+const SURVIVAL_LOOKAHEAD: usize = 500;
+
+/// Simple policy for computer controlled snakes: among all moves that do not immediately kill the
+/// snake prefer those leading into an area large enough to fit the snake, then head for the closest
+/// fruit, keeping the current direction on ties.
+pub fn choose_direction(game_state: &GameState, snake_index: usize, ruleset: &Ruleset) -> Direction {
+    if game_state.mode == GameMode::TRON {
+        return choose_survival_direction(game_state, snake_index, ruleset);
+    }
+
+    let snake = &game_state.snakes[snake_index];
+    let occupied = game_state.create_occupancy_matrix();
+    let required_space = snake.tail_x_pos.len() + 1;
+    let mut best: Option<(Direction, bool, u16)> = None;
+
+    for direction in [snake.direction, Direction::UP, Direction::DOWN, Direction::LEFT, Direction::RIGHT].iter() {
+        // reversing would run into the tail
+        if *direction == Direction::STOP || (*direction == snake.direction.opposite() && !snake.tail_x_pos.is_empty()) {
+            continue;
+        }
+
+        let next_pos = match next_position(game_state, snake.x_pos, snake.y_pos, direction, ruleset) {
+            Some(pos) if !occupied[pos.1 as usize][pos.0 as usize] => pos,
+            _ => continue
+        };
+
+        let has_space = count_reachable_fields(game_state, &occupied, next_pos, ruleset, required_space) >= required_space;
+        let fruit_distance = game_state.fruits.iter()
+            .map(|fruit| distance(next_pos, (fruit.x_pos, fruit.y_pos)))
+            .min()
+            .unwrap_or(0);
+
+        let is_better = match best {
+            Some((_, best_has_space, best_distance)) => (has_space && !best_has_space) || (has_space == best_has_space && fruit_distance < best_distance),
+            None => true
+        };
+        if is_better {
+            best = Some((*direction, has_space, fruit_distance));
+        }
+    }
+
+    match best {
+        Some((direction, _, _)) => direction,
+        // no safe move left, keep going and accept fate
+        None if snake.direction == Direction::STOP => Direction::UP,
+        None => snake.direction
+    }
+}
+
+/// Policy for light cycles: there is no fruit and the trail never retracts, so simply pick the move
+/// leaving the most space, keeping the current direction on ties
+fn choose_survival_direction(game_state: &GameState, snake_index: usize, ruleset: &Ruleset) -> Direction {
+    let snake = &game_state.snakes[snake_index];
+    let occupied = game_state.create_occupancy_matrix();
+    let mut best: Option<(Direction, usize)> = None;
+
+    for direction in [snake.direction, Direction::UP, Direction::DOWN, Direction::LEFT, Direction::RIGHT].iter() {
+        if *direction == Direction::STOP || *direction == snake.direction.opposite() {
+            continue;
+        }
+
+        let next_pos = match next_position(game_state, snake.x_pos, snake.y_pos, direction, ruleset) {
+            Some(pos) if !occupied[pos.1 as usize][pos.0 as usize] => pos,
+            _ => continue
+        };
+        let space = count_reachable_fields(game_state, &occupied, next_pos, ruleset, SURVIVAL_LOOKAHEAD);
+        if best.is_none_or(|(_, best_space)| space > best_space) {
+            best = Some((*direction, space));
+        }
+    }
+
+    best.map(|(direction, _)| direction).unwrap_or(snake.direction)
+}
+
+/// Returns the position the head would be moved to in the provided direction or None if it would
+/// hit a wall, wrapping around the field if the ruleset permits it
+pub(crate) fn next_position(game_state: &GameState, x_pos: u16, y_pos: u16, direction: &Direction, ruleset: &Ruleset) -> Option<(u16, u16)> {
+    let next_pos = match direction {
+        Direction::UP => (x_pos, y_pos - 1),
+        Direction::DOWN => (x_pos, y_pos + 1),
+        Direction::LEFT => (x_pos - 1, y_pos),
+        Direction::RIGHT => (x_pos + 1, y_pos),
+        Direction::STOP => (x_pos, y_pos),
+    };
+
+    if !game_state.is_wall(next_pos.0, next_pos.1) {
+        Some(next_pos)
+    } else if ruleset.is_game_over_on_wall_collision() {
+        None
+    } else {
+        Some(game_state.wrap_position(next_pos.0, next_pos.1))
+    }
+}
+
+/// Counts the free fields reachable from the provided position using a breadth first search, stops
+/// counting once the limit is reached
+pub(crate) fn count_reachable_fields(game_state: &GameState, occupied: &[Vec<bool>], start: (u16, u16), ruleset: &Ruleset, limit: usize) -> usize {
+    let mut visited = vec![vec![false; FIELD_WIDTH as usize]; FIELD_HEIGHT as usize];
+    let mut queue = VecDeque::new();
+    visited[start.1 as usize][start.0 as usize] = true;
+    queue.push_back(start);
+    let mut count = 0;
+
+    while let Some((x_pos, y_pos)) = queue.pop_front() {
+        count += 1;
+        if count >= limit {
+            break;
+        }
+
+        for direction in [Direction::UP, Direction::DOWN, Direction::LEFT, Direction::RIGHT].iter() {
+            if let Some((next_x, next_y)) = next_position(game_state, x_pos, y_pos, direction, ruleset) {
+                if !visited[next_y as usize][next_x as usize] && !occupied[next_y as usize][next_x as usize] {
+                    visited[next_y as usize][next_x as usize] = true;
+                    queue.push_back((next_x, next_y));
+                }
+            }
+        }
+    }
+
+    count
+}
+
+pub(crate) fn distance(a: (u16, u16), b: (u16, u16)) -> u16 {
+    a.0.max(b.0) - a.0.min(b.0) + a.1.max(b.1) - a.1.min(b.1)
+}
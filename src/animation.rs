@@ -0,0 +1,400 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::time::Duration;
+
+use crate::{render_field, FIELD_HEIGHT, FIELD_WIDTH, FRUIT_SYMBOL, HEAD_SYMBOL, RIVAL_HEAD_SYMBOL, RIVAL_TAIL_SYMBOL, TAIL_SYMBOL, WALL_SYMBOL};
+use crate::duration_formatter::DurationFormatter;
+use crate::gif::GifWriter;
+use crate::game_state::GameState;
+use crate::replay::{ExportOptions, Replay};
+
+/// Palette indices of the things drawn, in the order of `Theme::get_palette`
+const BACKGROUND: u8 = 0;
+const WALL: u8 = 1;
+const HEAD: u8 = 2;
+const TAIL: u8 = 3;
+const FRUIT: u8 = 4;
+const RIVAL: u8 = 5;
+const TEXT: u8 = 6;
+
+/// How long the last frame of a GIF is shown before it starts over, in hundredths of a second
+const FINAL_FRAME_DELAY: u64 = 200;
+
+/// Rows of the 3x5 pixel glyphs used for the HUD strip of GIFs, the highest of the 3 bits is the
+/// left pixel
+const GLYPHS: [(char, [u8; 5]); 19] = [
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b001, 0b001]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    ('C', [0b111, 0b100, 0b100, 0b100, 0b111]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('O', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+];
+
+pub type Color = [u8; 3];
+
+/// x, y, width and height of a rectangle in pixels
+type Bounds = (usize, usize, usize, usize);
+
+/// Start and end of the time something is shown in milliseconds, open intervals last until the end
+type Interval = (u64, Option<u64>);
+
+/// Colors of an exported animation
+pub struct Theme {
+    pub(crate) background: Color,
+    pub(crate) wall: Color,
+    pub(crate) head: Color,
+    pub(crate) tail: Color,
+    pub(crate) fruit: Color,
+    /// color of the heads and tails of the other snakes
+    pub(crate) rival: Color,
+    /// color of the HUD strip text
+    pub(crate) text: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            background: [0x10, 0x14, 0x18],
+            wall: [0x5c, 0x63, 0x70],
+            head: [0x98, 0xc3, 0x79],
+            tail: [0x4e, 0x8a, 0x3e],
+            fruit: [0xe0, 0x6c, 0x75],
+            rival: [0x61, 0xaf, 0xef],
+            text: [0xd7, 0xda, 0xe0],
+        }
+    }
+}
+
+impl Theme {
+    /// Parses a color written as `#rrggbb`
+    pub fn parse_color(value: &str) -> Result<Color, String> {
+        let error = || format!("'{}' is not a color, expected #rrggbb", value);
+        let hex = value.strip_prefix('#').filter(|hex| hex.len() == 6 && hex.is_ascii()).ok_or_else(error)?;
+        let mut color = [0; 3];
+        for (i, channel) in color.iter_mut().enumerate() {
+            *channel = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| error())?;
+        }
+
+        Ok(color)
+    }
+
+    fn get_palette(&self) -> Vec<Color> {
+        vec![self.background, self.wall, self.head, self.tail, self.fruit, self.rival, self.text]
+    }
+}
+
+/// What is shown on one tick of the replay
+struct Frame {
+    /// time since the start of the replay in milliseconds
+    time: u64,
+    /// palette index of each field, row by row
+    cells: Vec<u8>,
+    score_text: String,
+    time_text: String,
+}
+
+/// Plays the replay and yields the frame of every tick, including the one before the first move
+struct Frames<'a> {
+    replay: &'a Replay,
+    game_state: GameState,
+    tick: usize,
+}
+
+impl<'a> Frames<'a> {
+    fn new(replay: &'a Replay) -> Self {
+        Frames { replay, game_state: replay.create_game_state(), tick: 0 }
+    }
+}
+
+impl Iterator for Frames<'_> {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        if self.tick > self.replay.inputs.len() {
+            return None;
+        }
+        if self.tick > 0 {
+            self.replay.play_tick(&mut self.game_state, self.tick - 1);
+        }
+
+        let time = self.tick as u64 * self.replay.get_tick_delay();
+        let cells = render_field(&self.game_state, None).into_iter()
+            .flatten()
+            .map(|symbol| match symbol {
+                WALL_SYMBOL => WALL,
+                HEAD_SYMBOL => HEAD,
+                TAIL_SYMBOL => TAIL,
+                FRUIT_SYMBOL => FRUIT,
+                RIVAL_HEAD_SYMBOL | RIVAL_TAIL_SYMBOL => RIVAL,
+                _ => BACKGROUND,
+            })
+            .collect();
+        self.tick += 1;
+
+        Some(Frame {
+            time,
+            cells,
+            score_text: format!("SCORE {}", self.game_state.current_score),
+            time_text: format!("TIME {}", Duration::from_millis(time).format_duration()),
+        })
+    }
+}
+
+/// Sizes in pixels shared by both formats
+struct Layout {
+    cell_size: usize,
+    /// size of a pixel of the HUD glyphs
+    text_scale: usize,
+    field_height: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Layout {
+    fn new(options: &ExportOptions) -> Self {
+        let cell_size = options.cell_size as usize;
+        let text_scale = (cell_size / 3).max(1);
+        let field_height = FIELD_HEIGHT as usize * cell_size;
+        // the glyphs are 5 pixels high with a pixel of space above and below
+        let hud_height = if options.hud { 7 * text_scale } else { 0 };
+
+        Layout {
+            cell_size,
+            text_scale,
+            field_height,
+            width: FIELD_WIDTH as usize * cell_size,
+            height: field_height + hud_height,
+        }
+    }
+}
+
+/// Writes the replay as a GIF that loops forever. Each frame only holds the rectangle that changed
+/// since the previous tick, frames without changes extend the delay of the previous one instead.
+pub fn export_gif(replay: &Replay, file_path: &str, options: &ExportOptions) -> Result<(), String> {
+    let write_error = |error: std::io::Error| format!("could not write '{}': {}", file_path, error);
+    let layout = Layout::new(options);
+    let mut writer = GifWriter::create(file_path, layout.width as u16, layout.height as u16, &options.theme.get_palette())
+        .map_err(write_error)?;
+
+    // nothing has been drawn yet, so the first frame differs everywhere
+    let mut previous_canvas = vec![u8::MAX; layout.width * layout.height];
+    // the last frame with changes and its start time in hundredths of a second, it is written once
+    // it is known how long it is shown
+    let mut pending: Option<(Bounds, Vec<u8>, u64)> = None;
+    let mut end_time = 0;
+    for frame in Frames::new(replay) {
+        let canvas = draw_canvas(&frame, &layout, options.hud);
+        end_time = (frame.time + 5) / 10;
+        let bounds = match get_changed_bounds(&previous_canvas, &canvas, layout.width) {
+            Some(bounds) => bounds,
+            None => continue,
+        };
+
+        if let Some(((x, y, width, height), pixels, start_time)) = pending.take() {
+            writer.add_frame(x as u16, y as u16, width as u16, height as u16, &pixels, (end_time - start_time) as u16)
+                .map_err(write_error)?;
+        }
+        let (x, y, width, height) = bounds;
+        let pixels = (y..y + height).flat_map(|row| canvas[row * layout.width + x..row * layout.width + x + width].to_vec()).collect();
+        pending = Some((bounds, pixels, end_time));
+        previous_canvas = canvas;
+    }
+    if let Some(((x, y, width, height), pixels, start_time)) = pending {
+        let delay = (end_time - start_time + FINAL_FRAME_DELAY).min(u16::MAX as u64);
+        writer.add_frame(x as u16, y as u16, width as u16, height as u16, &pixels, delay as u16).map_err(write_error)?;
+    }
+
+    writer.finish().map_err(write_error)
+}
+
+/// Draws the frame as palette indices, row by row
+fn draw_canvas(frame: &Frame, layout: &Layout, hud: bool) -> Vec<u8> {
+    let mut canvas = vec![BACKGROUND; layout.width * layout.height];
+    for (i, cell) in frame.cells.iter().enumerate() {
+        let x = i % FIELD_WIDTH as usize * layout.cell_size;
+        let y = i / FIELD_WIDTH as usize * layout.cell_size;
+        for row in y..y + layout.cell_size {
+            canvas[row * layout.width + x..row * layout.width + x + layout.cell_size].fill(*cell);
+        }
+    }
+
+    if hud {
+        let scale = layout.text_scale;
+        let y = layout.field_height + scale;
+        draw_text(&mut canvas, layout, scale, y, &frame.score_text);
+        let time_x = layout.width.saturating_sub(frame.time_text.len() * 4 * scale);
+        draw_text(&mut canvas, layout, time_x, y, &frame.time_text);
+    }
+
+    canvas
+}
+
+/// Draws the text with the HUD glyphs, pixels outside of the canvas are cut off
+fn draw_text(canvas: &mut [u8], layout: &Layout, x: usize, y: usize, text: &str) {
+    let scale = layout.text_scale;
+    for (i, c) in text.chars().enumerate() {
+        let rows = match GLYPHS.iter().find(|(glyph, _)| *glyph == c) {
+            Some((_, rows)) => rows,
+            None => continue,
+        };
+
+        for (glyph_y, bits) in rows.iter().enumerate() {
+            for glyph_x in 0..3 {
+                if bits & (0b100 >> glyph_x) == 0 {
+                    continue;
+                }
+                let pixel_x = x + (i * 4 + glyph_x) * scale;
+                let pixel_y = y + glyph_y * scale;
+                for row in pixel_y..(pixel_y + scale).min(layout.height) {
+                    for column in pixel_x..(pixel_x + scale).min(layout.width) {
+                        canvas[row * layout.width + column] = TEXT;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the smallest rectangle holding all changed pixels
+fn get_changed_bounds(previous_canvas: &[u8], canvas: &[u8], width: usize) -> Option<Bounds> {
+    let mut bounds: Option<(usize, usize, usize, usize)> = None;
+    for (i, (previous, pixel)) in previous_canvas.iter().zip(canvas.iter()).enumerate() {
+        if previous == pixel {
+            continue;
+        }
+        let (x, y) = (i % width, i / width);
+        bounds = Some(match bounds {
+            Some((min_x, min_y, max_x, max_y)) => (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)),
+            None => (x, y, x, y),
+        });
+    }
+
+    bounds.map(|(min_x, min_y, max_x, max_y)| (min_x, min_y, max_x - min_x + 1, max_y - min_y + 1))
+}
+
+/// Writes the replay as an SVG animated with SMIL, which plays once and stays on the last frame.
+/// Every field gets a rect per color it ever shows that is only visible while it has that color.
+pub fn export_svg(replay: &Replay, file_path: &str, options: &ExportOptions) -> Result<(), String> {
+    let layout = Layout::new(options);
+    let palette = options.theme.get_palette();
+    // the ticks on which a field, or a line of the HUD, starts and stops showing something
+    let mut cell_intervals: BTreeMap<(usize, u8), Vec<Interval>> = BTreeMap::new();
+    let mut text_intervals: BTreeMap<(usize, String), Vec<Interval>> = BTreeMap::new();
+    let mut previous: Option<Frame> = None;
+
+    for frame in Frames::new(replay) {
+        for (i, cell) in frame.cells.iter().enumerate() {
+            let previous_cell = previous.as_ref().map(|previous| previous.cells[i]);
+            if previous_cell == Some(*cell) {
+                continue;
+            }
+            if let Some(previous_cell) = previous_cell.filter(|previous_cell| *previous_cell != BACKGROUND) {
+                close_interval(cell_intervals.get_mut(&(i, previous_cell)), frame.time);
+            }
+            if *cell != BACKGROUND {
+                cell_intervals.entry((i, *cell)).or_default().push((frame.time, None));
+            }
+        }
+
+        for (line, text) in [&frame.score_text, &frame.time_text].iter().copied().enumerate() {
+            let previous_text = previous.as_ref().map(|previous| if line == 0 { &previous.score_text } else { &previous.time_text });
+            if previous_text == Some(text) {
+                continue;
+            }
+            if let Some(previous_text) = previous_text {
+                close_interval(text_intervals.get_mut(&(line, previous_text.clone())), frame.time);
+            }
+            text_intervals.entry((line, text.clone())).or_default().push((frame.time, None));
+        }
+
+        previous = Some(frame);
+    }
+
+    let mut svg = String::new();
+    let title = format!("rusty_snake {} ({}), score {}", replay.mode, replay.ruleset, replay.score);
+    writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" shape-rendering=\"crispEdges\">", layout.width, layout.height)
+        .expect("writing to a string cannot fail");
+    writeln!(svg, "<title>{}</title>", escape_xml(&title)).expect("writing to a string cannot fail");
+    svg.push_str("<style>");
+    for (i, color) in palette.iter().enumerate() {
+        write!(svg, ".c{}{{fill:{}}}", i, format_color(color)).expect("writing to a string cannot fail");
+    }
+    write!(svg, "text{{font-family:monospace;font-size:{}px}}", 6 * layout.text_scale).expect("writing to a string cannot fail");
+    svg.push_str("</style>\n");
+    writeln!(svg, "<rect class=\"c{}\" width=\"{}\" height=\"{}\"/>", BACKGROUND, layout.width, layout.height).expect("writing to a string cannot fail");
+
+    for ((i, cell), intervals) in cell_intervals.iter() {
+        let x = i % FIELD_WIDTH as usize * layout.cell_size;
+        let y = i / FIELD_WIDTH as usize * layout.cell_size;
+        let element = format!("rect class=\"c{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"", cell, x, y, layout.cell_size, layout.cell_size);
+        write_animated_element(&mut svg, &element, "rect", "", intervals);
+    }
+    if options.hud {
+        let y = layout.field_height + 6 * layout.text_scale;
+        for ((line, text), intervals) in text_intervals.iter() {
+            let element = if *line == 0 {
+                format!("text class=\"c{}\" x=\"{}\" y=\"{}\"", TEXT, layout.text_scale, y)
+            } else {
+                format!("text class=\"c{}\" x=\"{}\" y=\"{}\" text-anchor=\"end\"", TEXT, layout.width - layout.text_scale, y)
+            };
+            write_animated_element(&mut svg, &element, "text", &escape_xml(text), intervals);
+        }
+    }
+    svg.push_str("</svg>\n");
+
+    fs::write(file_path, svg).map_err(|error| format!("could not write '{}': {}", file_path, error))
+}
+
+fn close_interval(intervals: Option<&mut Vec<Interval>>, time: u64) {
+    if let Some((_, end)) = intervals.and_then(|intervals| intervals.last_mut()) {
+        *end = Some(time);
+    }
+}
+
+/// Writes an element that is only visible during the intervals
+fn write_animated_element(svg: &mut String, element: &str, name: &str, content: &str, intervals: &[Interval]) {
+    if intervals == [(0, None)] && content.is_empty() {
+        writeln!(svg, "<{}/>", element).expect("writing to a string cannot fail");
+        return;
+    } else if intervals == [(0, None)] {
+        writeln!(svg, "<{}>{}</{}>", element, content, name).expect("writing to a string cannot fail");
+        return;
+    }
+
+    write!(svg, "<{} visibility=\"hidden\">{}", element, content).expect("writing to a string cannot fail");
+    for (start, end) in intervals {
+        write!(svg, "<set attributeName=\"visibility\" to=\"visible\" begin=\"{}\"", format_seconds(*start)).expect("writing to a string cannot fail");
+        match end {
+            Some(end) => write!(svg, " dur=\"{}\"/>", format_seconds(end - start)),
+            None => write!(svg, " fill=\"freeze\"/>"),
+        }.expect("writing to a string cannot fail");
+    }
+    writeln!(svg, "</{}>", name).expect("writing to a string cannot fail");
+}
+
+fn format_seconds(milliseconds: u64) -> String {
+    format!("{}.{:03}s", milliseconds / 1000, milliseconds % 1000)
+}
+
+fn format_color(color: &Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+fn escape_xml(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
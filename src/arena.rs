@@ -0,0 +1,107 @@
+use crate::{FIELD_HEIGHT, FIELD_WIDTH};
+use crate::death_cause::DeathCause;
+use crate::game_state::{GameState, PLAYER_INDEX};
+use crate::ruleset::Ruleset;
+use crate::snake::Snake;
+
+/// Number of computer controlled snakes the player competes against
+pub const RIVAL_COUNT: usize = 3;
+/// Number of fruits on the field at the same time
+pub const FRUIT_COUNT: usize = 3;
+/// Number of ticks between each step of the walls moving inwards
+pub const SHRINK_INTERVAL: u64 = 100;
+/// The walls stop moving once the inner field is this small
+pub const MIN_FIELD_SIZE: u16 = 12;
+/// Number of fruits the player is awarded as bonus for being the last snake alive
+pub const WIN_BONUS_FRUITS: u64 = 10;
+
+/// Spawns the player in the middle of the field and the rivals in the corners around it
+pub fn spawn_snakes() -> Vec<Snake> {
+    let mut snakes = vec![Snake::new()];
+    let spawn_positions = [
+        (FIELD_WIDTH / 4, FIELD_HEIGHT / 4),
+        (FIELD_WIDTH * 3 / 4, FIELD_HEIGHT / 4),
+        (FIELD_WIDTH / 4, FIELD_HEIGHT * 3 / 4),
+        (FIELD_WIDTH * 3 / 4, FIELD_HEIGHT * 3 / 4),
+    ];
+
+    for (x_pos, y_pos) in spawn_positions.iter().take(RIVAL_COUNT) {
+        let mut rival = Snake::spawn_at(*x_pos, *y_pos);
+        rival.ai_controlled = true;
+        snakes.push(rival);
+    }
+
+    snakes
+}
+
+/// Moves the walls inwards every SHRINK_INTERVAL ticks and ends the game once the player died or is
+/// the last snake alive
+pub fn handle_arena_tick(game_state: &mut GameState, ruleset: &Ruleset) {
+    let inner_size = FIELD_WIDTH.min(FIELD_HEIGHT) - 2 - 2 * game_state.wall_inset;
+    if game_state.tick_count.is_multiple_of(SHRINK_INTERVAL) && inner_size > MIN_FIELD_SIZE {
+        game_state.wall_inset += 1;
+
+        // snakes caught by the walls are crushed, dead snakes keep the cause they died of
+        for i in 0..game_state.snakes.len() {
+            let snake = &game_state.snakes[i];
+            let crushed = snake.alive && (game_state.is_wall(snake.x_pos, snake.y_pos)
+                || snake.tail_x_pos.iter().zip(snake.tail_y_pos.iter())
+                .any(|(x, y)| *x < FIELD_WIDTH && *y < FIELD_HEIGHT && game_state.is_wall(*x, *y)));
+            if crushed {
+                game_state.snakes[i].kill(DeathCause::Crushed);
+            }
+        }
+
+        let occupied = game_state.create_occupancy_matrix();
+        for i in 0..game_state.fruits.len() {
+            let fruit = &game_state.fruits[i];
+            if game_state.is_wall(fruit.x_pos, fruit.y_pos) {
+                let wall_inset = game_state.wall_inset;
+                game_state.fruits[i].respawn(wall_inset, &occupied, &mut game_state.rng);
+            }
+        }
+    }
+
+    let rivals_alive = count_rivals_alive(game_state);
+    if !game_state.player().alive {
+        game_state.game_over = true;
+    } else if rivals_alive == 0 {
+        game_state.won = true;
+        game_state.current_score += WIN_BONUS_FRUITS * game_state.get_fruit_points(ruleset);
+        game_state.game_over = true;
+    }
+}
+
+pub fn count_rivals_alive(game_state: &GameState) -> usize {
+    game_state.snakes.iter()
+        .enumerate()
+        .filter(|(i, snake)| *i != PLAYER_INDEX && snake.alive)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_mode::GameMode;
+
+    #[test]
+    fn dead_snakes_caught_by_the_walls_keep_their_death_cause() {
+        let ruleset = Ruleset::built_in().into_iter().find(|ruleset| ruleset.id == "NORMAL").expect("NORMAL is built in");
+        let mut game_state = GameState::new(GameMode::ARENA, Vec::new());
+        // one rival died next to the wall, another one is alive there
+        for (i, death_cause) in [(1, Some(DeathCause::HeadOn)), (2, None)] {
+            let rival = &mut game_state.snakes[i];
+            (rival.x_pos, rival.y_pos) = (1, 10 + i as u16);
+            rival.tail_x_pos.clear();
+            rival.tail_y_pos.clear();
+            if let Some(death_cause) = death_cause {
+                rival.kill(death_cause);
+            }
+        }
+        game_state.tick_count = SHRINK_INTERVAL;
+
+        handle_arena_tick(&mut game_state, &ruleset);
+        assert_eq!(game_state.snakes[1].death_cause, Some(DeathCause::HeadOn));
+        assert_eq!(game_state.snakes[2].death_cause, Some(DeathCause::Crushed));
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::Duration;
+
+use crate::{render_frame, WINDOW_COLS, WINDOW_ROWS};
+use crate::replay::Replay;
+
+/// Writes the replay as an asciicast v2 recording, as played by asciinema: a header line followed by
+/// one line per frame holding its time in seconds and the terminal output drawing it. Frames are
+/// spaced by the tick delay of the ruleset, so the recording plays at the speed of the original game.
+pub fn export(replay: &Replay, file_path: &str) -> Result<(), String> {
+    let write_error = |error: std::io::Error| format!("could not write '{}': {}", file_path, error);
+    let mut file = BufWriter::new(File::create(file_path).map_err(write_error)?);
+    let title = format!("rusty_snake {} ({}), score {}", replay.mode, replay.ruleset, replay.score);
+    writeln!(file, "{{\"version\": 2, \"width\": {}, \"height\": {}, \"title\": \"{}\"}}", WINDOW_COLS, WINDOW_ROWS, escape_json(&title))
+        .map_err(write_error)?;
+
+    let delay = replay.get_tick_delay();
+    let mut game_state = replay.create_game_state();
+    let mut previous_frame = String::new();
+    for tick in 0..=replay.inputs.len() {
+        let elapsed = Duration::from_millis(tick as u64 * delay);
+        let frame = render_frame(&game_state, "-", &replay.ruleset, elapsed, None);
+        let output = if tick == 0 {
+            // hide the cursor and clear the screen before drawing the first frame in full
+            format!("\x1b[?25l\x1b[2J\x1b[H{}", frame.replace('\n', "\r\n"))
+        } else {
+            get_changed_lines_output(&previous_frame, &frame)
+        };
+        writeln!(file, "[{:.3}, \"o\", \"{}\"]", elapsed.as_secs_f64(), escape_json(&output)).map_err(write_error)?;
+
+        if tick < replay.inputs.len() {
+            replay.play_tick(&mut game_state, tick);
+        }
+        previous_frame = frame;
+    }
+
+    file.flush().map_err(write_error)
+}
+
+/// Returns the output redrawing only the lines that differ from the previous frame, most ticks only
+/// change a few fields and HUD lines
+fn get_changed_lines_output(previous_frame: &str, frame: &str) -> String {
+    let previous_lines: Vec<&str> = previous_frame.split('\n').collect();
+    let lines: Vec<&str> = frame.split('\n').collect();
+    let mut output = String::new();
+
+    for row in 0..previous_lines.len().max(lines.len()) {
+        let line = lines.get(row).copied().unwrap_or("");
+        if previous_lines.get(row).copied().unwrap_or("") != line {
+            // move to the start of the row, write the line and erase what is left of the old one
+            write!(output, "\x1b[{};1H{}\x1b[K", row + 1, line).expect("writing to a string cannot fail");
+        }
+    }
+
+    output
+}
+
+fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).expect("writing to a string cannot fail"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
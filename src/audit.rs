@@ -0,0 +1,78 @@
+use crate::game_mode::GameMode;
+use crate::replay::{self, Replay};
+use crate::ruleset::Ruleset;
+use crate::score_manager::{Score, ScoreManager};
+
+/// Re-simulates the replay of the score and returns every way in which the score does not match
+/// it, the score is verified if there are none. The replay must have been played on the leaderboard
+/// of the score with its official rules, so editing the rulesets file does not verify old scores.
+pub fn verify_score(leaderboard_id: &str, score: &Score, rulesets: &[Ruleset]) -> Vec<String> {
+    let replay = match &score.replay {
+        Some(file_path) => match Replay::read(file_path) {
+            Ok(replay) => replay,
+            Err(message) => return vec![message],
+        },
+        None => return vec![String::from("no replay")],
+    };
+
+    let mut problems = Vec::new();
+    let replay_leaderboard_id = replay.mode.get_leaderboard_id(&replay.ruleset);
+    if replay_leaderboard_id != leaderboard_id {
+        problems.push(format!("replay was played on leaderboard {}", replay_leaderboard_id));
+    }
+    let official_ruleset = match (&replay.mode, &replay.puzzle_level) {
+        (GameMode::PUZZLE, Some(level_id)) => replay::find_puzzle_level(level_id).map(|level| level.to_ruleset()),
+        _ => rulesets.iter().find(|ruleset| ruleset.id == replay.ruleset.id).cloned(),
+    };
+    match official_ruleset {
+        Some(ruleset) if !ruleset.has_same_rules(&replay.ruleset) => problems.push(format!("replay was played with other rules than {}", ruleset.id)),
+        Some(_) => {}
+        None => problems.push(format!("unknown ruleset {}", replay.ruleset.id)),
+    }
+    if replay.mutators != score.mutators {
+        problems.push(String::from("replay was played with other mutators"));
+    }
+
+    let game_state = replay.simulate();
+    if score.aborted {
+        problems.push(String::from("game was aborted"));
+    } else if !game_state.game_over {
+        problems.push(String::from("game in the replay does not end"));
+    }
+    if game_state.current_score != score.score {
+        problems.push(format!("replay scores {}", game_state.current_score));
+    }
+    let tail_length = game_state.player().tail_x_pos.len() as u64;
+    if score.tail_length != Some(tail_length) {
+        problems.push(format!("tail length in the replay is {}", tail_length));
+    }
+
+    // each tick is followed by the refresh delay, so a game cannot be over any sooner
+    let min_time = game_state.tick_count.saturating_sub(1) * replay.ruleset.get_refresh_delay();
+    if score.time != Some(replay.time) {
+        problems.push(format!("replay took {} ms", replay.time));
+    } else if replay.time < min_time {
+        problems.push(format!("{} ticks take at least {} ms", game_state.tick_count, min_time));
+    }
+
+    problems
+}
+
+/// Verifies every score in the scores file, lists the ones that do not match their replay and
+/// updates the verified flags accordingly
+pub fn run(score_manager: &ScoreManager, rulesets: &[Ruleset]) {
+    let scores = score_manager.get_scores_by_leaderboard();
+    let mut flags = Vec::with_capacity(scores.len());
+
+    for (leaderboard_id, score) in scores.iter() {
+        let problems = verify_score(leaderboard_id, score, rulesets);
+        if !problems.is_empty() {
+            println!("{:<16}{:<16}{:>8}  {}", leaderboard_id, score.user, score.score, problems.join(", "));
+        }
+        flags.push(problems.is_empty());
+    }
+    score_manager.write_verified_flags(&flags);
+
+    let verified_count = flags.iter().filter(|verified| **verified).count();
+    println!("{} of {} scores verified", verified_count, scores.len());
+}
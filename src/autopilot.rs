@@ -0,0 +1,261 @@
+use std::collections::VecDeque;
+
+use crate::{ai, FIELD_HEIGHT, FIELD_WIDTH};
+use crate::controller::{Controller, Observation};
+use crate::direction::Direction;
+use crate::game_mode::GameMode;
+use crate::game_state::GameState;
+use crate::ruleset::Ruleset;
+use crate::snake::Snake;
+
+/// A field moved onto along a path and the direction of the move
+pub(crate) type Step = ((u16, u16), Direction);
+
+/// Steers the snake along the shortest path to a fruit, but only if the snake could still reach the
+/// end of its tail after eating, which means it can always escape by following its tail. Otherwise it
+/// chases its tail until a safe path opens up.
+pub struct AutopilotController;
+
+impl Controller for AutopilotController {
+    fn next_direction(&mut self, observation: &Observation) -> Direction {
+        let game_state = observation.game_state();
+        let ruleset = observation.ruleset();
+        // light cycles never eat, the trail never retracts so there is no tail to follow either
+        if game_state.mode == GameMode::TRON || game_state.fruits.is_empty() {
+            return ai::choose_direction(game_state, observation.snake_index(), ruleset);
+        }
+
+        let snake = observation.snake();
+        let body = Body::of(snake);
+        let others = create_others_matrix(game_state, observation.snake_index());
+
+        let blocked = body.create_blocked_matrix(&others, body.is_growing());
+        if let Some(path) = find_safe_fruit_path_of(game_state, ruleset, &body, &others, &blocked) {
+            return path[0].1;
+        }
+
+        choose_tail_chasing_direction(game_state, ruleset, &body, &others, &blocked)
+            .unwrap_or_else(|| ai::choose_direction(game_state, observation.snake_index(), ruleset))
+    }
+}
+
+/// Returns the shortest path of the snake to a fruit after which it can still reach the end of its
+/// tail, None if there is no such path
+pub(crate) fn find_safe_fruit_path(game_state: &GameState, ruleset: &Ruleset, snake_index: usize) -> Option<Vec<Step>> {
+    let body = Body::of(&game_state.snakes[snake_index]);
+    let others = create_others_matrix(game_state, snake_index);
+    let blocked = body.create_blocked_matrix(&others, body.is_growing());
+
+    find_safe_fruit_path_of(game_state, ruleset, &body, &others, &blocked)
+}
+
+/// Returns true if moving in the direction leads the snake into a pocket it cannot get out of: the
+/// end of its tail is out of reach and there are fewer free fields left than the snake is long.
+/// Moving into a wall or a snake is an immediate crash rather than a dead end.
+pub(crate) fn is_dead_end(game_state: &GameState, ruleset: &Ruleset, snake_index: usize, direction: Direction) -> bool {
+    let body = Body::of(&game_state.snakes[snake_index]);
+    let others = create_others_matrix(game_state, snake_index);
+    let blocked = body.create_blocked_matrix(&others, body.is_growing());
+
+    let next_pos = match ai::next_position(game_state, body.head().0, body.head().1, &direction, ruleset) {
+        Some(pos) if direction != Direction::STOP && !blocked[pos.1 as usize][pos.0 as usize] => pos,
+        _ => return false
+    };
+    let next_body = body.follow(&[(next_pos, direction)]);
+    if next_body.can_reach_tail(game_state, ruleset, &others) {
+        return false;
+    }
+
+    let occupied = next_body.create_blocked_matrix(&others, true);
+    // the head is counted as well, so the snake needs one field more than its length
+    ai::count_reachable_fields(game_state, &occupied, next_pos, ruleset, next_body.length + 1) <= next_body.length
+}
+
+fn find_safe_fruit_path_of(game_state: &GameState, ruleset: &Ruleset, body: &Body, others: &[Vec<bool>], blocked: &[Vec<bool>]) -> Option<Vec<Step>> {
+    let path = find_path(game_state, ruleset, body.head(), |pos| blocked[pos.1 as usize][pos.0 as usize], |pos| {
+        game_state.fruits.iter().any(|fruit| fruit.x_pos == pos.0 && fruit.y_pos == pos.1)
+    })?;
+
+    let mut fed_body = body.follow(&path);
+    // the growth of the meal enters the field behind the tail, whose end stays in place meanwhile
+    fed_body.length += game_state.get_growth_per_fruit(ruleset) as usize;
+    if fed_body.can_reach_tail(game_state, ruleset, others) {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// The fields occupied by a snake, head first. Elements of the tail that have not entered the field
+/// yet count towards the length but do not occupy a field.
+#[derive(Clone)]
+struct Body {
+    fields: Vec<(u16, u16)>,
+    length: usize,
+}
+
+impl Body {
+    fn of(snake: &Snake) -> Body {
+        let mut fields = vec![(snake.x_pos, snake.y_pos)];
+        fields.extend(snake.tail_x_pos.iter().zip(snake.tail_y_pos.iter())
+            .map(|(x_pos, y_pos)| (*x_pos, *y_pos))
+            .filter(|(x_pos, y_pos)| *x_pos < FIELD_WIDTH && *y_pos < FIELD_HEIGHT));
+
+        Body {
+            fields,
+            length: snake.tail_x_pos.len() + 1,
+        }
+    }
+
+    fn head(&self) -> (u16, u16) {
+        self.fields[0]
+    }
+
+    fn tail_end(&self) -> (u16, u16) {
+        self.fields[self.fields.len() - 1]
+    }
+
+    /// Returns true if the end of the tail stays in place on the next move
+    fn is_growing(&self) -> bool {
+        self.fields.len() < self.length
+    }
+
+    /// Returns a matrix indexed by y and x that is true for every field occupied by other snakes or
+    /// this one, the end of the tail is only included if requested
+    fn create_blocked_matrix(&self, others: &[Vec<bool>], include_tail_end: bool) -> Vec<Vec<bool>> {
+        let mut matrix = others.to_vec();
+        let blocking_fields = if include_tail_end { self.fields.len() } else { self.fields.len() - 1 };
+        for (x_pos, y_pos) in self.fields[..blocking_fields].iter() {
+            matrix[*y_pos as usize][*x_pos as usize] = true;
+        }
+
+        matrix
+    }
+
+    /// Returns the body after moving along the path
+    fn follow(&self, path: &[Step]) -> Body {
+        let mut fields: Vec<(u16, u16)> = path.iter().rev().map(|(pos, _)| *pos).collect();
+        fields.extend(self.fields.iter());
+        fields.truncate(self.length);
+
+        Body {
+            fields,
+            length: self.length,
+        }
+    }
+
+    /// Returns true if there is a path from the head to the end of the tail. While the snake grows the
+    /// end of the tail stays in place, so the path has to take more moves than the growth left: it
+    /// leads to a field next to the end that is at least as many moves away as the growth left.
+    fn can_reach_tail(&self, game_state: &GameState, ruleset: &Ruleset, others: &[Vec<bool>]) -> bool {
+        if self.fields.len() < 2 {
+            return true;
+        }
+
+        let tail_end = self.tail_end();
+        let growth_left = self.length - self.fields.len();
+        let blocked = self.create_blocked_matrix(others, true);
+        [Direction::UP, Direction::DOWN, Direction::LEFT, Direction::RIGHT].iter()
+            .filter_map(|direction| ai::next_position(game_state, tail_end.0, tail_end.1, direction, ruleset))
+            .any(|pos| {
+                if pos == self.head() {
+                    return growth_left == 0;
+                }
+                !blocked[pos.1 as usize][pos.0 as usize]
+                    && find_path(game_state, ruleset, self.head(), |pos| blocked[pos.1 as usize][pos.0 as usize], |other| other == pos)
+                        .is_some_and(|path| path.len() >= growth_left)
+            })
+    }
+}
+
+/// Picks the safe move after which the tail stays reachable, preferring the one furthest away from
+/// the end of the tail to stall as long as possible
+fn choose_tail_chasing_direction(game_state: &GameState, ruleset: &Ruleset, body: &Body, others: &[Vec<bool>], blocked: &[Vec<bool>]) -> Option<Direction> {
+    let mut best: Option<(Direction, u16)> = None;
+
+    for direction in [Direction::UP, Direction::DOWN, Direction::LEFT, Direction::RIGHT].iter() {
+        let next_pos = match ai::next_position(game_state, body.head().0, body.head().1, direction, ruleset) {
+            Some(pos) if !blocked[pos.1 as usize][pos.0 as usize] => pos,
+            _ => continue
+        };
+
+        let next_body = body.follow(&[(next_pos, *direction)]);
+        if !next_body.can_reach_tail(game_state, ruleset, others) {
+            continue;
+        }
+
+        let tail_distance = ai::distance(next_pos, next_body.tail_end());
+        if best.is_none_or(|(_, best_distance)| tail_distance > best_distance) {
+            best = Some((*direction, tail_distance));
+        }
+    }
+
+    best.map(|(direction, _)| direction)
+}
+
+/// Returns a matrix indexed by y and x that is true for every field occupied by a snake other than
+/// the one with the provided index
+fn create_others_matrix(game_state: &GameState, snake_index: usize) -> Vec<Vec<bool>> {
+    let mut matrix = vec![vec![false; FIELD_WIDTH as usize]; FIELD_HEIGHT as usize];
+
+    for (i, snake) in game_state.snakes.iter().enumerate() {
+        if i == snake_index || !snake.alive {
+            continue;
+        }
+
+        for (x_pos, y_pos) in Body::of(snake).fields {
+            matrix[y_pos as usize][x_pos as usize] = true;
+        }
+    }
+
+    matrix
+}
+
+/// Breadth first search for the shortest path from the start to a field matching the goal, returns
+/// each field along the path with the direction moving onto it, excluding the start
+fn find_path(
+    game_state: &GameState,
+    ruleset: &Ruleset,
+    start: (u16, u16),
+    is_blocked: impl Fn((u16, u16)) -> bool,
+    is_goal: impl Fn((u16, u16)) -> bool,
+) -> Option<Vec<Step>> {
+    // the field each field was first reached from and the direction of that move
+    let mut came_from: Vec<Vec<Option<Step>>> = vec![vec![None; FIELD_WIDTH as usize]; FIELD_HEIGHT as usize];
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+
+    while let Some(pos) = queue.pop_front() {
+        for direction in [Direction::UP, Direction::DOWN, Direction::LEFT, Direction::RIGHT].iter() {
+            let next_pos = match ai::next_position(game_state, pos.0, pos.1, direction, ruleset) {
+                Some(next_pos) => next_pos,
+                None => continue
+            };
+            if next_pos == start || came_from[next_pos.1 as usize][next_pos.0 as usize].is_some() || is_blocked(next_pos) {
+                continue;
+            }
+
+            came_from[next_pos.1 as usize][next_pos.0 as usize] = Some((pos, *direction));
+            if is_goal(next_pos) {
+                return Some(reconstruct_path(&came_from, start, next_pos));
+            }
+            queue.push_back(next_pos);
+        }
+    }
+
+    None
+}
+
+fn reconstruct_path(came_from: &[Vec<Option<Step>>], start: (u16, u16), goal: (u16, u16)) -> Vec<Step> {
+    let mut path = Vec::new();
+    let mut pos = goal;
+
+    while pos != start {
+        let (previous, direction) = came_from[pos.1 as usize][pos.0 as usize].expect("every field on the path has a predecessor");
+        path.push((pos, direction));
+        pos = previous;
+    }
+    path.reverse();
+
+    path
+}
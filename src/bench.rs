@@ -0,0 +1,184 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+use crate::controller::{self, Controller, Observation};
+use crate::death_cause::DeathCause;
+use crate::duration_formatter::DurationFormatter;
+use crate::game_mode::GameMode;
+use crate::game_state::{GameState, PLAYER_INDEX};
+use crate::mutator::Mutator;
+use crate::ruleset::Ruleset;
+use crate::tron::TronOpponent;
+
+/// Options of the `bench-bot` command
+pub struct BenchOptions {
+    pub(crate) controller: String,
+    pub(crate) games: u64,
+    pub(crate) threads: usize,
+    /// seed of the first game, each further game uses the next seed
+    pub(crate) seed: u64,
+    pub(crate) mode: GameMode,
+    pub(crate) ruleset_id: String,
+    pub(crate) mutators: Vec<Mutator>,
+    /// games still running after this many ticks are stopped
+    pub(crate) max_ticks: u64,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        BenchOptions {
+            controller: String::new(),
+            games: 1000,
+            threads: thread::available_parallelism().map(|threads| threads.get()).unwrap_or(1),
+            seed: 0,
+            mode: GameMode::CLASSIC,
+            ruleset_id: String::from("NORMAL"),
+            mutators: Vec::new(),
+            max_ticks: 100_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Outcome {
+    Died(DeathCause),
+    Disqualified(String),
+    Won,
+    TickLimit,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Died(death_cause) => write!(f, "{}", death_cause),
+            Outcome::Disqualified(reason) => write!(f, "disqualified: {}", reason),
+            Outcome::Won => write!(f, "won"),
+            Outcome::TickLimit => write!(f, "tick limit reached"),
+        }
+    }
+}
+
+struct GameResult {
+    score: u64,
+    tail_length: u64,
+    ticks: u64,
+    outcome: Outcome,
+}
+
+/// Plays the games of the benchmark spread across the threads and prints the distribution of the
+/// results, nothing is rendered and there are no delays between ticks
+pub fn run(options: &BenchOptions, ruleset: &Ruleset) {
+    let started = Instant::now();
+    let next_game = AtomicU64::new(0);
+    let results = Mutex::new(Vec::with_capacity(options.games as usize));
+
+    thread::scope(|scope| {
+        for _ in 0..options.threads {
+            scope.spawn(|| loop {
+                let game = next_game.fetch_add(1, Ordering::Relaxed);
+                if game >= options.games {
+                    break;
+                }
+
+                let mut controller = controller::create_bot(&options.controller).expect("controller checked when parsing the options");
+                let result = play_game(options, ruleset, controller.as_mut(), options.seed.wrapping_add(game));
+                results.lock().expect("no benchmark thread panicked").push(result);
+            });
+        }
+    });
+
+    let results = results.into_inner().expect("no benchmark thread panicked");
+    print_report(options, ruleset, &results, started);
+}
+
+fn play_game(options: &BenchOptions, ruleset: &Ruleset, controller: &mut dyn Controller, seed: u64) -> GameResult {
+    let mut game_state = match options.mode {
+        GameMode::TRON => GameState::new_tron(TronOpponent::NONE, options.mutators.clone()),
+        mode => GameState::new(mode, options.mutators.clone()),
+    };
+    game_state.reseed(seed);
+
+    // counting the iterations instead of the ticks makes sure a bot pausing the game cannot stall it
+    for _ in 0..options.max_ticks {
+        if game_state.game_over {
+            break;
+        }
+
+        let direction = controller.next_direction(&Observation::new(&game_state, ruleset, PLAYER_INDEX));
+        if controller.get_disqualification().is_some() {
+            game_state.disqualify(PLAYER_INDEX);
+            break;
+        }
+        game_state.player_mut().direction = direction;
+        game_state.handle_snake_movement(ruleset);
+    }
+
+    let player = game_state.player();
+    let outcome = match player.death_cause {
+        Some(DeathCause::Disqualified) => Outcome::Disqualified(String::from(controller.get_disqualification().unwrap_or_default())),
+        Some(death_cause) => Outcome::Died(death_cause),
+        None if game_state.won => Outcome::Won,
+        None => Outcome::TickLimit,
+    };
+
+    GameResult {
+        score: game_state.current_score,
+        tail_length: player.tail_x_pos.len() as u64,
+        ticks: game_state.tick_count,
+        outcome,
+    }
+}
+
+fn print_report(options: &BenchOptions, ruleset: &Ruleset, results: &[GameResult], started: Instant) {
+    println!(
+        "{} games of {} ({} {}, mutators: {}), seeds {} to {}, {} thread(s), took {}",
+        results.len(),
+        options.controller,
+        options.mode,
+        ruleset.id,
+        Mutator::format_list(&options.mutators),
+        options.seed,
+        options.seed.wrapping_add(options.games.saturating_sub(1)),
+        options.threads,
+        started.elapsed().format_duration(),
+    );
+    if results.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("{:<16}{:>10}{:>10}{:>10}{:>10}{:>10}{:>10}{:>10}", "", "mean", "min", "p10", "median", "p90", "p99", "max");
+    print_distribution("Score", results.iter().map(|result| result.score).collect());
+    print_distribution("Tail length", results.iter().map(|result| result.tail_length).collect());
+    print_distribution("Ticks survived", results.iter().map(|result| result.ticks).collect());
+
+    let mut outcomes: Vec<(Outcome, usize)> = Vec::new();
+    for result in results {
+        match outcomes.iter_mut().find(|(outcome, _)| *outcome == result.outcome) {
+            Some((_, count)) => *count += 1,
+            None => outcomes.push((result.outcome.clone(), 1)),
+        }
+    }
+    outcomes.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+    println!();
+    println!("Outcome");
+    for (outcome, count) in outcomes {
+        println!("  {:<40}{:>8}{:>9.1}%", outcome.to_string(), count, count as f64 * 100.0 / results.len() as f64);
+    }
+}
+
+fn print_distribution(label: &str, mut values: Vec<u64>) {
+    values.sort_unstable();
+    let mean = values.iter().sum::<u64>() as f64 / values.len() as f64;
+    // nearest rank percentile
+    let percentile = |p: usize| values[((values.len() * p).div_ceil(100)).max(1) - 1];
+
+    println!(
+        "{:<16}{:>10.1}{:>10}{:>10}{:>10}{:>10}{:>10}{:>10}",
+        label, mean, values[0], percentile(10), percentile(50), percentile(90), percentile(99), values[values.len() - 1],
+    );
+}
@@ -0,0 +1,397 @@
+use std::str::FromStr;
+
+use crate::animation::Theme;
+use crate::bench::BenchOptions;
+use crate::controller;
+use crate::dataset::DatasetOptions;
+use crate::snake_env::{EnvBenchOptions, ObservationKind};
+use crate::game_mode::GameMode;
+use crate::mutator::Mutator;
+use crate::replay::{ExportOptions, ReplayOptions};
+use crate::tournament::{TournamentFormat, TournamentOptions};
+
+pub const USAGE: &str = "usage:
+  rusty_snake [play] [--controller <name> | --autoplay] [--seed <n> | --challenge <code>] [--ghost]
+                     [--dataset <file> [--observation <kind>] [--radius <n>]]
+  rusty_snake bench-bot --controller <name> [--games <n>] [--threads <n>] [--seed <n>] [--mode <mode>]
+                        [--ruleset <id>] [--mutators <id,...>] [--max-ticks <n>]
+  rusty_snake bench-env [--observation grid|egocentric|raycast] [--radius <n>] [--steps <n>] [--seed <n>]
+                        [--ruleset <id>]
+  rusty_snake tournament --controller <name> --controller <name> [--controller <name> ...]
+                         [--format versus|arena] [--seeds <n>] [--seed <n>] [--ruleset <id>]
+                         [--max-ticks <n>] [--ratings <file>]
+  rusty_snake dataset --controller <name> --output <file> [--games <n>] [--threads <n>] [--seed <n>]
+                      [--ruleset <id>] [--observation grid|egocentric|raycast] [--radius <n>]
+                      [--sample-rate <0..1>] [--dedup] [--max-ticks <n>]
+  rusty_snake replay <file> | --code <code>
+  rusty_snake replay share <file> | --code <code>
+  rusty_snake replay export <file> [--asciicast <file>] [--svg <file>] [--gif <file>] [--cell-size <n>]
+                            [--hud] [--<background|wall|head|tail|fruit|rival|text>-color <#rrggbb>]
+  rusty_snake scores audit";
+
+/// Radius of egocentric observations unless specified
+const DEFAULT_RADIUS: u16 = 5;
+/// Size of a field of exported animations in pixels unless specified
+const DEFAULT_CELL_SIZE: u16 = 8;
+/// Largest size of a field of exported animations, which are then 5000 pixels wide
+const MAX_CELL_SIZE: u16 = 100;
+
+/// What to do as specified on the command line
+pub enum Command {
+    Play(PlayOptions),
+    BenchBot(BenchOptions),
+    BenchEnv(EnvBenchOptions),
+    Tournament(TournamentOptions),
+    Dataset(DatasetOptions),
+    Replay(ReplayOptions),
+    ShareReplay(ReplayOptions),
+    ExportReplay(ExportOptions),
+    AuditScores,
+}
+
+pub struct PlayOptions {
+    /// name of the controller steering the player's snake
+    pub(crate) controller: String,
+    /// file the moves of classic games are recorded to
+    pub(crate) dataset: Option<String>,
+    pub(crate) observation_kind: ObservationKind,
+    /// seed every game is started from instead of a random one
+    pub(crate) seed: Option<u64>,
+    /// whether to race against the player's best run
+    pub(crate) ghost: bool,
+    /// share code of the game to start from instead of choosing one in the menus
+    pub(crate) challenge: Option<String>,
+}
+
+impl Command {
+    /// Parses the arguments without the program name
+    pub fn parse(args: impl Iterator<Item=String>) -> Result<Command, String> {
+        let mut args = args.peekable();
+        match args.peek().map(|arg| arg.as_str()) {
+            Some("play") => {
+                args.next();
+                Ok(Command::Play(parse_play_options(args)?))
+            }
+            Some("bench-bot") => {
+                args.next();
+                Ok(Command::BenchBot(parse_bench_options(args)?))
+            }
+            Some("bench-env") => {
+                args.next();
+                Ok(Command::BenchEnv(parse_env_bench_options(args)?))
+            }
+            Some("tournament") => {
+                args.next();
+                Ok(Command::Tournament(parse_tournament_options(args)?))
+            }
+            Some("dataset") => {
+                args.next();
+                Ok(Command::Dataset(parse_dataset_options(args)?))
+            }
+            Some("replay") => {
+                args.next();
+                if args.peek().map(|arg| arg.as_str()) == Some("export") {
+                    args.next();
+                    return Ok(Command::ExportReplay(parse_export_options(args)?));
+                }
+                if args.peek().map(|arg| arg.as_str()) == Some("share") {
+                    args.next();
+                    return Ok(Command::ShareReplay(parse_replay_options(args)?));
+                }
+                Ok(Command::Replay(parse_replay_options(args)?))
+            }
+            Some("scores") => {
+                args.next();
+                match args.next().as_deref() {
+                    Some("audit") => {}
+                    Some(subcommand) => return Err(format!("unknown scores command '{}'", subcommand)),
+                    None => return Err(String::from("scores requires a command")),
+                }
+                match args.next() {
+                    Some(arg) => Err(format!("unknown argument '{}'", arg)),
+                    None => Ok(Command::AuditScores),
+                }
+            }
+            _ => Ok(Command::Play(parse_play_options(args)?)),
+        }
+    }
+}
+
+fn parse_play_options(mut args: impl Iterator<Item=String>) -> Result<PlayOptions, String> {
+    let mut options = PlayOptions {
+        controller: String::from(controller::KEYBOARD_CONTROLLER),
+        dataset: None,
+        observation_kind: ObservationKind::Raycast,
+        seed: None,
+        ghost: false,
+        challenge: None,
+    };
+    let mut observation = String::from("raycast");
+    let mut radius = DEFAULT_RADIUS;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--controller" => options.controller = parse_controller(next_value(&mut args, &arg)?)?,
+            "--autoplay" => options.controller = String::from(controller::AUTOPILOT_CONTROLLER),
+            "--dataset" => options.dataset = Some(next_value(&mut args, &arg)?),
+            "--observation" => observation = next_value(&mut args, &arg)?,
+            "--radius" => radius = parse_number(&arg, &next_value(&mut args, &arg)?)?,
+            "--seed" => options.seed = Some(parse_number(&arg, &next_value(&mut args, &arg)?)?),
+            "--ghost" => options.ghost = true,
+            "--challenge" => options.challenge = Some(next_value(&mut args, &arg)?),
+            _ => return Err(format!("unknown argument '{}'", arg)),
+        }
+    }
+    if options.seed.is_some() && options.challenge.is_some() {
+        return Err(String::from("--seed and --challenge can not be combined"));
+    }
+    options.observation_kind = parse_observation_kind(&observation, radius)?;
+
+    Ok(options)
+}
+
+fn parse_bench_options(mut args: impl Iterator<Item=String>) -> Result<BenchOptions, String> {
+    let mut options = BenchOptions::default();
+    let mut controller = None;
+
+    while let Some(arg) = args.next() {
+        let value = next_value(&mut args, &arg)?;
+        match arg.as_str() {
+            "--controller" => controller = Some(parse_controller(value)?),
+            "--games" => options.games = parse_number(&arg, &value)?,
+            "--threads" => options.threads = parse_number(&arg, &value)?,
+            "--seed" => options.seed = parse_number(&arg, &value)?,
+            "--mode" => options.mode = parse_mode(&value)?,
+            "--ruleset" => options.ruleset_id = value,
+            "--mutators" => options.mutators = parse_mutators(&value)?,
+            "--max-ticks" => options.max_ticks = parse_number(&arg, &value)?,
+            _ => return Err(format!("unknown argument '{}'", arg)),
+        }
+    }
+
+    options.controller = match controller {
+        Some(name) if name == controller::KEYBOARD_CONTROLLER => return Err(String::from("bench-bot requires a bot as controller")),
+        Some(name) => name,
+        None => return Err(String::from("bench-bot requires --controller")),
+    };
+    if options.threads == 0 {
+        return Err(String::from("--threads must be at least 1"));
+    }
+    Mutator::check_mode(&options.mutators, options.mode)?;
+
+    Ok(options)
+}
+
+fn parse_env_bench_options(mut args: impl Iterator<Item=String>) -> Result<EnvBenchOptions, String> {
+    let mut options = EnvBenchOptions::default();
+    let mut observation = String::from("raycast");
+    let mut radius = DEFAULT_RADIUS;
+
+    while let Some(arg) = args.next() {
+        let value = next_value(&mut args, &arg)?;
+        match arg.as_str() {
+            "--observation" => observation = value,
+            "--radius" => radius = parse_number(&arg, &value)?,
+            "--steps" => options.steps = parse_number(&arg, &value)?,
+            "--seed" => options.seed = parse_number(&arg, &value)?,
+            "--ruleset" => options.ruleset_id = value,
+            _ => return Err(format!("unknown argument '{}'", arg)),
+        }
+    }
+
+    options.observation_kind = parse_observation_kind(&observation, radius)?;
+
+    Ok(options)
+}
+
+fn parse_tournament_options(mut args: impl Iterator<Item=String>) -> Result<TournamentOptions, String> {
+    let mut options = TournamentOptions::default();
+
+    while let Some(arg) = args.next() {
+        let value = next_value(&mut args, &arg)?;
+        match arg.as_str() {
+            "--controller" => options.controllers.push(parse_controller(value)?),
+            "--format" => options.format = match value.as_str() {
+                "versus" => TournamentFormat::VERSUS,
+                "arena" => TournamentFormat::ARENA,
+                _ => return Err(format!("unknown format '{}'", value)),
+            },
+            "--seeds" => options.seeds = parse_number(&arg, &value)?,
+            "--seed" => options.seed = parse_number(&arg, &value)?,
+            "--ruleset" => options.ruleset_id = value,
+            "--max-ticks" => options.max_ticks = parse_number(&arg, &value)?,
+            "--ratings" => options.ratings_file = value,
+            _ => return Err(format!("unknown argument '{}'", arg)),
+        }
+    }
+
+    if options.controllers.len() < 2 {
+        return Err(String::from("tournament requires at least two --controller"));
+    }
+    if options.controllers.iter().any(|name| name == controller::KEYBOARD_CONTROLLER) {
+        return Err(String::from("tournament requires bots as controllers"));
+    }
+    for (i, name) in options.controllers.iter().enumerate() {
+        if options.controllers[..i].contains(name) {
+            return Err(format!("controller '{}' is entered twice", name));
+        }
+    }
+    if options.seeds == 0 {
+        return Err(String::from("--seeds must be at least 1"));
+    }
+
+    Ok(options)
+}
+
+fn parse_dataset_options(mut args: impl Iterator<Item=String>) -> Result<DatasetOptions, String> {
+    let mut options = DatasetOptions::default();
+    let mut controller = None;
+    let mut output = None;
+    let mut observation = String::from("raycast");
+    let mut radius = DEFAULT_RADIUS;
+
+    while let Some(arg) = args.next() {
+        // the only flag without a value
+        if arg == "--dedup" {
+            options.dedup = true;
+            continue;
+        }
+
+        let value = next_value(&mut args, &arg)?;
+        match arg.as_str() {
+            "--controller" => controller = Some(parse_controller(value)?),
+            "--output" => output = Some(value),
+            "--games" => options.games = parse_number(&arg, &value)?,
+            "--threads" => options.threads = parse_number(&arg, &value)?,
+            "--seed" => options.seed = parse_number(&arg, &value)?,
+            "--ruleset" => options.ruleset_id = value,
+            "--observation" => observation = value,
+            "--radius" => radius = parse_number(&arg, &value)?,
+            "--sample-rate" => options.sample_rate = parse_number(&arg, &value)?,
+            "--max-ticks" => options.max_ticks = parse_number(&arg, &value)?,
+            _ => return Err(format!("unknown argument '{}'", arg)),
+        }
+    }
+
+    options.controller = match controller {
+        Some(name) if name == controller::KEYBOARD_CONTROLLER => return Err(String::from("dataset requires a bot as controller, record human games with --dataset when playing")),
+        Some(name) => name,
+        None => return Err(String::from("dataset requires --controller")),
+    };
+    options.output = output.ok_or("dataset requires --output")?;
+    options.observation_kind = parse_observation_kind(&observation, radius)?;
+    if options.threads == 0 {
+        return Err(String::from("--threads must be at least 1"));
+    }
+    if !(options.sample_rate > 0.0 && options.sample_rate <= 1.0) {
+        return Err(String::from("--sample-rate must be greater than 0 and at most 1"));
+    }
+
+    Ok(options)
+}
+
+fn parse_replay_options(mut args: impl Iterator<Item=String>) -> Result<ReplayOptions, String> {
+    let mut options = ReplayOptions { file: None, code: None };
+    match args.next() {
+        Some(arg) if arg == "--code" => options.code = Some(next_value(&mut args, &arg)?),
+        Some(arg) if !arg.starts_with("--") => options.file = Some(arg),
+        Some(arg) => return Err(format!("unknown argument '{}'", arg)),
+        None => return Err(String::from("replay requires a file or --code")),
+    }
+    if let Some(arg) = args.next() {
+        return Err(format!("unknown argument '{}'", arg));
+    }
+
+    Ok(options)
+}
+
+fn parse_export_options(mut args: impl Iterator<Item=String>) -> Result<ExportOptions, String> {
+    let mut file = None;
+    let mut asciicast = None;
+    let mut svg = None;
+    let mut gif = None;
+    let mut cell_size = DEFAULT_CELL_SIZE;
+    let mut theme = Theme::default();
+    let mut hud = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--asciicast" => asciicast = Some(next_value(&mut args, &arg)?),
+            "--svg" => svg = Some(next_value(&mut args, &arg)?),
+            "--gif" => gif = Some(next_value(&mut args, &arg)?),
+            "--cell-size" => {
+                cell_size = parse_number(&arg, &next_value(&mut args, &arg)?)?;
+                if !(1..=MAX_CELL_SIZE).contains(&cell_size) {
+                    return Err(format!("--cell-size must be between 1 and {}", MAX_CELL_SIZE));
+                }
+            }
+            "--hud" => hud = true,
+            "--background-color" => theme.background = Theme::parse_color(&next_value(&mut args, &arg)?)?,
+            "--wall-color" => theme.wall = Theme::parse_color(&next_value(&mut args, &arg)?)?,
+            "--head-color" => theme.head = Theme::parse_color(&next_value(&mut args, &arg)?)?,
+            "--tail-color" => theme.tail = Theme::parse_color(&next_value(&mut args, &arg)?)?,
+            "--fruit-color" => theme.fruit = Theme::parse_color(&next_value(&mut args, &arg)?)?,
+            "--rival-color" => theme.rival = Theme::parse_color(&next_value(&mut args, &arg)?)?,
+            "--text-color" => theme.text = Theme::parse_color(&next_value(&mut args, &arg)?)?,
+            _ if arg.starts_with("--") => return Err(format!("unknown argument '{}'", arg)),
+            _ if file.is_none() => file = Some(arg),
+            _ => return Err(format!("unknown argument '{}'", arg)),
+        }
+    }
+
+    let options = ExportOptions {
+        file: file.ok_or("replay export requires a file")?,
+        asciicast,
+        svg,
+        gif,
+        cell_size,
+        theme,
+        hud,
+    };
+    if options.asciicast.is_none() && options.svg.is_none() && options.gif.is_none() {
+        return Err(String::from("replay export requires --asciicast, --svg or --gif"));
+    }
+
+    Ok(options)
+}
+
+fn next_value(args: &mut impl Iterator<Item=String>, flag: &str) -> Result<String, String> {
+    args.next().ok_or(format!("{} requires a value", flag))
+}
+
+fn parse_controller(name: String) -> Result<String, String> {
+    if !controller::exists(&name) {
+        return Err(format!("unknown controller '{}', available: {}, exec:<command>, wasm:<path>", name, controller::names().join(", ")));
+    }
+
+    Ok(name)
+}
+
+fn parse_number<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{} requires a number but got '{}'", flag, value))
+}
+
+/// Parses the modes that can be played without a menu, puzzle levels are chosen interactively
+fn parse_mode(value: &str) -> Result<GameMode, String> {
+    match GameMode::all().iter().find(|mode| mode.to_string().eq_ignore_ascii_case(value)) {
+        Some(GameMode::PUZZLE) => Err(String::from("puzzle mode is not supported")),
+        Some(mode) => Ok(*mode),
+        None => Err(format!("unknown mode '{}'", value)),
+    }
+}
+
+fn parse_observation_kind(name: &str, radius: u16) -> Result<ObservationKind, String> {
+    match name {
+        "grid" => Ok(ObservationKind::Grid),
+        "egocentric" => Ok(ObservationKind::Egocentric { radius }),
+        "raycast" => Ok(ObservationKind::Raycast),
+        _ => Err(format!("unknown observation '{}'", name)),
+    }
+}
+
+fn parse_mutators(value: &str) -> Result<Vec<Mutator>, String> {
+    value.split(',')
+        .filter(|id| !id.is_empty())
+        .map(|id| Mutator::from_id(id).ok_or(format!("unknown mutator '{}'", id)))
+        .collect()
+}
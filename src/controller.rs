@@ -0,0 +1,127 @@
+use crate::{ai, FIELD_HEIGHT, FIELD_WIDTH};
+use crate::autopilot::AutopilotController;
+use crate::direction::Direction;
+use crate::external::{EXTERNAL_CONTROLLER_PREFIX, ExternalController};
+use crate::game_state::GameState;
+use crate::hamiltonian::HamiltonianController;
+use crate::ruleset::Ruleset;
+use crate::snake::Snake;
+use crate::wasm::{WASM_CONTROLLER_PREFIX, WasmController};
+
+/// Name of the controller reading the keyboard, it is created by the UI since it needs the window
+pub const KEYBOARD_CONTROLLER: &str = "keyboard";
+/// Name of the pathfinding controller used by `--autoplay` and the demo on the title screen
+pub const AUTOPILOT_CONTROLLER: &str = "autopilot";
+
+/// Read-only view of the game handed to a controller each tick
+pub struct Observation<'a> {
+    game_state: &'a GameState,
+    ruleset: &'a Ruleset,
+    snake_index: usize,
+}
+
+impl<'a> Observation<'a> {
+    pub fn new(game_state: &'a GameState, ruleset: &'a Ruleset, snake_index: usize) -> Self {
+        Observation {
+            game_state,
+            ruleset,
+            snake_index,
+        }
+    }
+
+    /// Returns the whole board: every snake, the fruits, the walls and the active mutators
+    pub fn game_state(&self) -> &'a GameState {
+        self.game_state
+    }
+
+    pub fn ruleset(&self) -> &'a Ruleset {
+        self.ruleset
+    }
+
+    /// Returns the index of the controlled snake in the snakes of the game state
+    pub fn snake_index(&self) -> usize {
+        self.snake_index
+    }
+
+    /// Returns the controlled snake
+    pub fn snake(&self) -> &'a Snake {
+        &self.game_state.snakes[self.snake_index]
+    }
+
+    /// Encodes the observation as a single line of JSON, positions are [x, y] pairs and the tails list
+    /// only the elements that entered the field, starting behind the head
+    pub fn to_json(&self) -> String {
+        let game_state = self.game_state;
+        let position = |x_pos: u16, y_pos: u16| format!("[{},{}]", x_pos, y_pos);
+
+        let snakes: Vec<String> = game_state.snakes.iter()
+            .map(|snake| {
+                let tail: Vec<String> = snake.tail_x_pos.iter().zip(snake.tail_y_pos.iter())
+                    .filter(|(x_pos, y_pos)| **x_pos < FIELD_WIDTH && **y_pos < FIELD_HEIGHT)
+                    .map(|(x_pos, y_pos)| position(*x_pos, *y_pos))
+                    .collect();
+                format!(
+                    "{{\"head\":{},\"tail\":[{}],\"length\":{},\"direction\":\"{}\",\"alive\":{}}}",
+                    position(snake.x_pos, snake.y_pos), tail.join(","), snake.tail_x_pos.len() + 1, snake.direction, snake.alive,
+                )
+            })
+            .collect();
+        let fruits: Vec<String> = game_state.fruits.iter().map(|fruit| position(fruit.x_pos, fruit.y_pos)).collect();
+
+        format!(
+            "{{\"tick\":{},\"mode\":\"{}\",\"width\":{},\"height\":{},\"wall_inset\":{},\"wall_wrap\":{},\"you\":{},\"score\":{},\"snakes\":[{}],\"fruits\":[{}]}}",
+            game_state.tick_count, game_state.mode, FIELD_WIDTH, FIELD_HEIGHT, game_state.wall_inset,
+            !self.ruleset.is_game_over_on_wall_collision(), self.snake_index, game_state.current_score,
+            snakes.join(","), fruits.join(","),
+        )
+    }
+}
+
+/// Steers a snake: called once per tick with the current state of the game, the returned direction
+/// is applied before the snake moves
+pub trait Controller {
+    fn next_direction(&mut self, observation: &Observation) -> Direction;
+
+    /// Returns the reason if the controller broke the rules, its snake is then taken out of the game
+    fn get_disqualification(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Steers the snake with the built-in policy of the rival snakes in arena mode
+pub struct GreedyController;
+
+impl Controller for GreedyController {
+    fn next_direction(&mut self, observation: &Observation) -> Direction {
+        ai::choose_direction(observation.game_state(), observation.snake_index(), observation.ruleset())
+    }
+}
+
+/// Returns the names of all controllers that can be chosen at startup, the keyboard comes first
+pub fn names() -> [&'static str; 4] {
+    [KEYBOARD_CONTROLLER, "greedy", AUTOPILOT_CONTROLLER, "hamiltonian"]
+}
+
+/// Returns true if a controller with the provided name exists, external bots are named after the
+/// command starting them and WebAssembly bots after the path of their module
+pub fn exists(name: &str) -> bool {
+    names().contains(&name) || name.starts_with(EXTERNAL_CONTROLLER_PREFIX) || name.starts_with(WASM_CONTROLLER_PREFIX)
+}
+
+/// Creates the bot with the provided name, the keyboard controller is not included since it needs the
+/// window of the UI
+pub fn create_bot(name: &str) -> Option<Box<dyn Controller>> {
+    if let Some(command) = name.strip_prefix(EXTERNAL_CONTROLLER_PREFIX) {
+        return Some(Box::new(ExternalController::new(command)));
+    }
+    if let Some(path) = name.strip_prefix(WASM_CONTROLLER_PREFIX) {
+        return Some(Box::new(WasmController::new(path)));
+    }
+
+    match name {
+        "greedy" => Some(Box::new(GreedyController)),
+        AUTOPILOT_CONTROLLER => Some(Box::new(AutopilotController)),
+        "hamiltonian" => Some(Box::new(HamiltonianController::new())),
+        _ => None
+    }
+}
@@ -0,0 +1,309 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+use crate::controller::{self, Controller, Observation};
+use crate::direction::Direction;
+use crate::duration_formatter::DurationFormatter;
+use crate::game_mode::GameMode;
+use crate::game_state::{GameState, PLAYER_INDEX};
+use crate::npz::NpzWriter;
+use crate::rng::Rng;
+use crate::ruleset::Ruleset;
+use crate::snake_env::{ACTIONS, ObservationKind};
+
+/// Outcome of the game a sample was taken from: the snake died, the game was stopped before it was
+/// decided or the snake filled the board
+const OUTCOME_DIED: i8 = -1;
+const OUTCOME_UNDECIDED: i8 = 0;
+const OUTCOME_WON: i8 = 1;
+
+/// Options of the `dataset` command
+pub struct DatasetOptions {
+    pub(crate) controller: String,
+    pub(crate) output: String,
+    pub(crate) games: u64,
+    pub(crate) threads: usize,
+    /// seed of the first game, each further game uses the next seed
+    pub(crate) seed: u64,
+    pub(crate) ruleset_id: String,
+    pub(crate) observation_kind: ObservationKind,
+    /// share of the moves that are recorded
+    pub(crate) sample_rate: f64,
+    /// whether samples with the same observation and action as an earlier one are dropped
+    pub(crate) dedup: bool,
+    /// games still running after this many ticks are stopped
+    pub(crate) max_ticks: u64,
+}
+
+impl Default for DatasetOptions {
+    fn default() -> Self {
+        DatasetOptions {
+            controller: String::new(),
+            output: String::new(),
+            games: 100,
+            threads: thread::available_parallelism().map(|threads| threads.get()).unwrap_or(1),
+            seed: 0,
+            ruleset_id: String::from("NORMAL"),
+            observation_kind: ObservationKind::Raycast,
+            sample_rate: 1.0,
+            dedup: false,
+            max_ticks: 100_000,
+        }
+    }
+}
+
+/// The samples taken from a single game
+pub struct GameRecording {
+    /// encoded observations, see Dataset::get_row_size
+    observations: Vec<u8>,
+    actions: Vec<u8>,
+    /// tick of the game each sample was taken at
+    ticks: Vec<u64>,
+    final_tick: u64,
+    outcome: i8,
+}
+
+impl GameRecording {
+    fn new() -> Self {
+        GameRecording {
+            observations: Vec::new(),
+            actions: Vec::new(),
+            ticks: Vec::new(),
+            final_tick: 0,
+            outcome: OUTCOME_UNDECIDED,
+        }
+    }
+}
+
+/// Records the observation of the player before each move together with the direction it moves in,
+/// every move is recorded with the probability of the sample rate
+pub struct DatasetRecorder {
+    observation_kind: ObservationKind,
+    sample_rate: f64,
+    rng: Rng,
+    buffer: Vec<f32>,
+    recording: GameRecording,
+}
+
+impl DatasetRecorder {
+    pub fn new(observation_kind: ObservationKind, sample_rate: f64, seed: u64) -> Self {
+        DatasetRecorder {
+            observation_kind,
+            sample_rate,
+            rng: Rng::new(seed),
+            buffer: vec![0.0; observation_kind.size()],
+            recording: GameRecording::new(),
+        }
+    }
+
+    /// Records the state before the move and the direction the player moves in
+    pub fn record(&mut self, game_state: &GameState, action: Direction) {
+        let action_code = match ACTIONS.iter().position(|direction| *direction == action) {
+            Some(code) => code as u8,
+            None => return
+        };
+        // sampling before encoding so that skipped moves cost next to nothing
+        if self.sample_rate < 1.0 && self.rng.gen_range(0, 1_000_000) as f64 >= self.sample_rate * 1_000_000.0 {
+            return;
+        }
+
+        self.observation_kind.encode(game_state, &mut self.buffer, true);
+        if self.observation_kind.is_binary() {
+            // eight values per byte, the first one in the highest bit as numpy.unpackbits expects
+            for chunk in self.buffer.chunks(8) {
+                let byte = chunk.iter().enumerate().fold(0u8, |byte, (i, value)| if *value > 0.0 { byte | (0x80 >> i) } else { byte });
+                self.recording.observations.push(byte);
+            }
+        } else {
+            for value in self.buffer.iter() {
+                self.recording.observations.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        self.recording.actions.push(action_code);
+        self.recording.ticks.push(game_state.tick_count);
+    }
+
+    /// Returns the samples of the game that just ended and starts recording the next one
+    pub fn finish_game(&mut self, game_state: &GameState) -> GameRecording {
+        let mut recording = mem::replace(&mut self.recording, GameRecording::new());
+        recording.final_tick = game_state.tick_count;
+        recording.outcome = if game_state.won {
+            OUTCOME_WON
+        } else if !game_state.player().alive {
+            OUTCOME_DIED
+        } else {
+            OUTCOME_UNDECIDED
+        };
+
+        recording
+    }
+}
+
+/// The samples of all recorded games, written as a `.npz` file
+pub struct Dataset {
+    observation_kind: ObservationKind,
+    dedup: bool,
+    /// hashes of the observations and actions of all samples
+    seen: HashSet<u64>,
+    duplicates: u64,
+    game_count: u32,
+    observations: Vec<u8>,
+    actions: Vec<u8>,
+    outcomes: Vec<u8>,
+    steps_left: Vec<u8>,
+    games: Vec<u8>,
+}
+
+impl Dataset {
+    pub fn new(observation_kind: ObservationKind, dedup: bool) -> Self {
+        Dataset {
+            observation_kind,
+            dedup,
+            seen: HashSet::new(),
+            duplicates: 0,
+            game_count: 0,
+            observations: Vec::new(),
+            actions: Vec::new(),
+            outcomes: Vec::new(),
+            steps_left: Vec::new(),
+            games: Vec::new(),
+        }
+    }
+
+    /// Returns the number of bytes of an encoded observation: binary observations are packed into
+    /// bits, all others are stored as f32
+    fn get_row_size(&self) -> usize {
+        if self.observation_kind.is_binary() {
+            self.observation_kind.size().div_ceil(8)
+        } else {
+            4 * self.observation_kind.size()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn add(&mut self, recording: &GameRecording) {
+        let row_size = self.get_row_size();
+
+        for (i, (action, tick)) in recording.actions.iter().zip(recording.ticks.iter()).enumerate() {
+            let observation = &recording.observations[i * row_size..(i + 1) * row_size];
+            if self.dedup {
+                let mut hasher = DefaultHasher::new();
+                observation.hash(&mut hasher);
+                action.hash(&mut hasher);
+                if !self.seen.insert(hasher.finish()) {
+                    self.duplicates += 1;
+                    continue;
+                }
+            }
+
+            self.observations.extend_from_slice(observation);
+            self.actions.push(*action);
+            self.outcomes.push(recording.outcome as u8);
+            self.steps_left.extend_from_slice(&((recording.final_tick - tick) as u32).to_le_bytes());
+            self.games.extend_from_slice(&self.game_count.to_le_bytes());
+        }
+        self.game_count += 1;
+    }
+
+    pub fn write(&self, file_path: &str) -> std::io::Result<()> {
+        let mut npz = NpzWriter::new();
+        let size = self.observation_kind.size();
+
+        if self.observation_kind.is_binary() {
+            npz.add_array("observations", "|u1", &[self.len(), self.get_row_size()], &self.observations);
+        } else {
+            npz.add_array("observations", "<f4", &[self.len(), size], &self.observations);
+        }
+        npz.add_array("observation_size", "<u4", &[], &(size as u32).to_le_bytes());
+        npz.add_array("actions", "|u1", &[self.len()], &self.actions);
+        npz.add_array("outcomes", "|i1", &[self.len()], &self.outcomes);
+        npz.add_array("steps_left", "<u4", &[self.len()], &self.steps_left);
+        npz.add_array("games", "<u4", &[self.len()], &self.games);
+
+        npz.write(file_path)
+    }
+}
+
+/// Plays the games with the bot spread across the threads, nothing is rendered, and writes the
+/// recorded samples in the order of the games so that the same options always produce the same file
+pub fn run(options: &DatasetOptions, ruleset: &Ruleset) {
+    let started = Instant::now();
+    let next_game = AtomicU64::new(0);
+    let recordings = Mutex::new(Vec::with_capacity(options.games as usize));
+
+    thread::scope(|scope| {
+        for _ in 0..options.threads {
+            scope.spawn(|| loop {
+                let game = next_game.fetch_add(1, Ordering::Relaxed);
+                if game >= options.games {
+                    break;
+                }
+
+                let mut controller = controller::create_bot(&options.controller).expect("controller checked when parsing the options");
+                let recording = record_game(options, ruleset, controller.as_mut(), options.seed.wrapping_add(game));
+                recordings.lock().expect("no dataset thread panicked").push((game, recording));
+            });
+        }
+    });
+
+    let mut recordings = recordings.into_inner().expect("no dataset thread panicked");
+    recordings.sort_by_key(|(game, _)| *game);
+    let mut dataset = Dataset::new(options.observation_kind, options.dedup);
+    for (_, recording) in recordings.iter() {
+        dataset.add(recording);
+    }
+    if let Err(error) = dataset.write(&options.output) {
+        eprintln!("could not write the dataset to '{}': {}", options.output, error);
+        process::exit(1);
+    }
+
+    println!(
+        "{} samples of {} games of {} ({}, {} observations), seeds {} to {}, took {}",
+        dataset.len(),
+        recordings.len(),
+        options.controller,
+        ruleset.id,
+        options.observation_kind,
+        options.seed,
+        options.seed.wrapping_add(options.games.saturating_sub(1)),
+        started.elapsed().format_duration(),
+    );
+    if options.dedup {
+        println!("{} duplicate samples dropped", dataset.duplicates);
+    }
+    println!("written to {}", options.output);
+}
+
+fn record_game(options: &DatasetOptions, ruleset: &Ruleset, controller: &mut dyn Controller, seed: u64) -> GameRecording {
+    let mut game_state = GameState::new(GameMode::CLASSIC, Vec::new());
+    game_state.reseed(seed);
+    let mut recorder = DatasetRecorder::new(options.observation_kind, options.sample_rate, seed);
+
+    // counting the iterations instead of the ticks makes sure a bot pausing the game cannot stall it
+    for _ in 0..options.max_ticks {
+        if game_state.game_over {
+            break;
+        }
+
+        let direction = controller.next_direction(&Observation::new(&game_state, ruleset, PLAYER_INDEX));
+        if controller.get_disqualification().is_some() {
+            game_state.disqualify(PLAYER_INDEX);
+            break;
+        }
+        recorder.record(&game_state, direction);
+        game_state.player_mut().direction = direction;
+        game_state.handle_snake_movement(ruleset);
+    }
+
+    recorder.finish_game(&game_state)
+}
@@ -0,0 +1,27 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeathCause {
+    Wall,
+    OwnTail,
+    OtherTail,
+    HeadOn,
+    /// caught by the walls moving inwards in arena mode
+    Crushed,
+    /// the controller of the snake broke the rules
+    Disqualified,
+}
+
+impl fmt::Display for DeathCause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            DeathCause::Wall => "hit the wall",
+            DeathCause::OwnTail => "bit its own tail",
+            DeathCause::OtherTail => "ran into another snake",
+            DeathCause::HeadOn => "head-on collision",
+            DeathCause::Crushed => "crushed by the walls",
+            DeathCause::Disqualified => "disqualified",
+        };
+        write!(f, "{}", description)
+    }
+}
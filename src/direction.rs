@@ -1,6 +1,7 @@
 use crate::fmt;
 
-#[derive(Debug, std::cmp::PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, std::cmp::PartialEq)]
 pub enum Direction {
     UP,
     DOWN,
@@ -13,4 +14,16 @@ impl fmt::Display for Direction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self)
     }
-}
\ No newline at end of file
+}
+
+impl Direction {
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::UP => Direction::DOWN,
+            Direction::DOWN => Direction::UP,
+            Direction::LEFT => Direction::RIGHT,
+            Direction::RIGHT => Direction::LEFT,
+            Direction::STOP => Direction::STOP,
+        }
+    }
+}
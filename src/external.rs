@@ -0,0 +1,134 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use crate::controller::{Controller, Observation};
+use crate::direction::Direction;
+
+/// Prefix of controller names running an external bot, followed by the command starting the bot
+pub const EXTERNAL_CONTROLLER_PREFIX: &str = "exec:";
+/// Time a bot has to answer each tick
+const MOVE_TIME_LIMIT: Duration = Duration::from_millis(200);
+/// Time a bot has to answer the first tick, which includes starting up
+const STARTUP_TIME_LIMIT: Duration = Duration::from_secs(5);
+
+/// Steers the snake with a bot running in a child process. Each tick the observation is written to
+/// the standard input of the bot as a single line of JSON, the bot answers with a line containing
+/// UP, DOWN, LEFT or RIGHT. A bot that does not answer in time, answers anything else, answers more
+/// than once or exits is disqualified. Its standard error is discarded, it would garble the screen.
+pub struct ExternalController {
+    command: String,
+    child: Option<Child>,
+    stdin: Option<ChildStdin>,
+    /// lines written by the bot, read on a separate thread so that waiting for them can time out
+    lines: Option<Receiver<String>>,
+    disqualification: Option<String>,
+}
+
+impl ExternalController {
+    pub fn new(command: &str) -> Self {
+        ExternalController {
+            command: String::from(command),
+            child: None,
+            stdin: None,
+            lines: None,
+            disqualification: None,
+        }
+    }
+
+    /// Starts the bot on the first tick, so that creating the controller to check its name is cheap
+    fn start(&mut self) -> Result<(), String> {
+        let mut parts = self.command.split_whitespace();
+        let program = parts.next().ok_or("no command to start the bot")?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|error| format!("could not start '{}': {}", self.command, error))?;
+
+        let stdout = child.stdout.take().expect("stdout of the bot is piped");
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            // stops once the bot exits or the controller is dropped
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        self.stdin = child.stdin.take();
+        self.lines = Some(receiver);
+        self.child = Some(child);
+        Ok(())
+    }
+
+    fn request_direction(&mut self, observation: &Observation) -> Result<Direction, String> {
+        let time_limit = if self.child.is_none() {
+            self.start()?;
+            STARTUP_TIME_LIMIT
+        } else {
+            MOVE_TIME_LIMIT
+        };
+
+        // every line has to answer an observation, a line left over would be taken as the next answer
+        match self.lines.as_ref().expect("bot has been started").try_recv() {
+            Ok(line) => return Err(format!("malformed answer '{}', only one line per observation is allowed", line)),
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => return Err(String::from("bot exited")),
+        }
+
+        let stdin = self.stdin.as_mut().expect("bot has been started");
+        writeln!(stdin, "{}", observation.to_json())
+            .and_then(|_| stdin.flush())
+            .map_err(|_| String::from("bot exited"))?;
+
+        let line = match self.lines.as_ref().expect("bot has been started").recv_timeout(time_limit) {
+            Ok(line) => line,
+            Err(RecvTimeoutError::Timeout) => return Err(format!("no answer within {} ms", time_limit.as_millis())),
+            Err(RecvTimeoutError::Disconnected) => return Err(String::from("bot exited")),
+        };
+
+        match line.trim().to_ascii_uppercase().as_str() {
+            "UP" => Ok(Direction::UP),
+            "DOWN" => Ok(Direction::DOWN),
+            "LEFT" => Ok(Direction::LEFT),
+            "RIGHT" => Ok(Direction::RIGHT),
+            _ => Err(format!("malformed answer '{}'", line)),
+        }
+    }
+}
+
+impl Controller for ExternalController {
+    fn next_direction(&mut self, observation: &Observation) -> Direction {
+        if self.disqualification.is_some() {
+            return observation.snake().direction;
+        }
+
+        match self.request_direction(observation) {
+            Ok(direction) => direction,
+            Err(reason) => {
+                self.disqualification = Some(reason);
+                observation.snake().direction
+            }
+        }
+    }
+
+    fn get_disqualification(&self) -> Option<&str> {
+        self.disqualification.as_deref()
+    }
+}
+
+impl Drop for ExternalController {
+    fn drop(&mut self) {
+        self.stdin = None;
+        if let Some(child) = self.child.as_mut() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
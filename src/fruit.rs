@@ -1,14 +1,19 @@
-use rand::Rng;
 use crate::{FIELD_WIDTH, FIELD_HEIGHT};
+use crate::rng::Rng;
 
+#[derive(Clone)]
 pub struct Fruit {
     pub(crate) x_pos: u16,
     pub(crate) y_pos: u16,
 }
 
 impl Fruit {
-    pub(crate) fn new() -> Fruit {
-        let rand_location = Self::generate_rand_location();
+    /// Creates a fruit at a random free location inside the walls, the wall inset is the number of
+    /// fields the walls have moved inwards (see arena mode) and the occupancy matrix marks the fields
+    /// taken by snakes
+    pub(crate) fn new(wall_inset: u16, occupied: &[Vec<bool>], rng: &mut Rng) -> Fruit {
+        let rand_location = Self::generate_rand_location(wall_inset, occupied, rng)
+            .expect("a new game has free fields");
 
         Fruit {
             x_pos: rand_location.0,
@@ -16,18 +21,41 @@ impl Fruit {
         }
     }
 
-    fn generate_rand_location() -> (u16, u16) {
-        let mut rng = rand::thread_rng();
+    /// Picks one of the free fields inside the walls or returns None if the snakes fill all of them
+    fn generate_rand_location(wall_inset: u16, occupied: &[Vec<bool>], rng: &mut Rng) -> Option<(u16, u16)> {
+        let free_fields: Vec<(u16, u16)> = (1 + wall_inset..FIELD_HEIGHT - 1 - wall_inset)
+            .flat_map(|y_pos| (1 + wall_inset..FIELD_WIDTH - 1 - wall_inset).map(move |x_pos| (x_pos, y_pos)))
+            .filter(|(x_pos, y_pos)| !occupied[*y_pos as usize][*x_pos as usize])
+            .collect();
+        if free_fields.is_empty() {
+            return None;
+        }
+
+        Some(free_fields[rng.gen_range(0, free_fields.len() as u64) as usize])
+    }
 
-        let pos_x = rng.gen_range(1, FIELD_WIDTH - 1);
-        let pos_y = rng.gen_range(1, FIELD_HEIGHT - 1);
+    /// Moves the fruit by one field in a random direction, the fruit stays in place if the chosen
+    /// field is a wall or marked in the occupancy matrix
+    pub(crate) fn drift(&mut self, wall_inset: u16, occupied: &[Vec<bool>], rng: &mut Rng) {
+        let (x_pos, y_pos) = match rng.gen_range(0, 4) {
+            0 if self.y_pos > 1 + wall_inset => (self.x_pos, self.y_pos - 1),
+            1 if self.y_pos < FIELD_HEIGHT - 2 - wall_inset => (self.x_pos, self.y_pos + 1),
+            2 if self.x_pos > 1 + wall_inset => (self.x_pos - 1, self.y_pos),
+            3 if self.x_pos < FIELD_WIDTH - 2 - wall_inset => (self.x_pos + 1, self.y_pos),
+            _ => return
+        };
 
-        (pos_x, pos_y)
+        if !occupied[y_pos as usize][x_pos as usize] {
+            self.x_pos = x_pos;
+            self.y_pos = y_pos;
+        }
     }
 
-    pub(crate) fn respawn(&mut self) {
-        let rand_location = Self::generate_rand_location();
-        self.x_pos = rand_location.0;
-        self.y_pos = rand_location.1;
+    /// Moves the fruit to a random free location, the fruit stays in place if there is none left
+    pub(crate) fn respawn(&mut self, wall_inset: u16, occupied: &[Vec<bool>], rng: &mut Rng) {
+        if let Some(rand_location) = Self::generate_rand_location(wall_inset, occupied, rng) {
+            self.x_pos = rand_location.0;
+            self.y_pos = rand_location.1;
+        }
     }
-}
\ No newline at end of file
+}
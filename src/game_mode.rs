@@ -0,0 +1,44 @@
+use std::fmt;
+
+use crate::ruleset::Ruleset;
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameMode {
+    CLASSIC,
+    ARENA,
+    PUZZLE,
+    TRON,
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl GameMode {
+    /// Returns all game modes in menu order
+    pub fn all() -> [GameMode; 4] {
+        [GameMode::CLASSIC, GameMode::ARENA, GameMode::PUZZLE, GameMode::TRON]
+    }
+
+    pub(crate) fn get_description(&self) -> &str {
+        match self {
+            GameMode::CLASSIC => "Eat as many fruits as possible",
+            GameMode::ARENA => "Compete for fruit against rival snakes while the walls close in, the last snake alive wins",
+            GameMode::PUZZLE => "Turn-based, the snake only moves when a key is pressed, eat all fruits within the move budget",
+            GameMode::TRON => "The tail never retracts and leaves a permanent trail, survive alone or versus another player or a bot",
+        }
+    }
+
+    /// Returns the id of the leaderboard scores achieved with the provided ruleset in this mode are
+    /// recorded in, classic games use the id of the ruleset so older scores stay valid and puzzle
+    /// levels provide their own ruleset named after the level
+    pub fn get_leaderboard_id(&self, ruleset: &Ruleset) -> String {
+        match self {
+            GameMode::CLASSIC => ruleset.id.clone(),
+            _ => format!("{}:{}", self, ruleset.id),
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use crate::{ai, arena, tron, FIELD_HEIGHT, FIELD_WIDTH};
+use crate::death_cause::DeathCause;
+use crate::direction::Direction::STOP;
+use crate::fruit::Fruit;
+use crate::game_mode::GameMode;
+use crate::mutator::{FRUIT_DRIFT_INTERVAL, Mutator};
+use crate::puzzle::{PuzzleLevel, UNUSED_MOVE_BONUS};
+use crate::rng::Rng;
+use crate::ruleset::Ruleset;
+use crate::snake::Snake;
+use crate::tron::TronOpponent;
+
+/// Index of the snake controlled by the player, all other snakes are rivals
+pub const PLAYER_INDEX: usize = 0;
+
+#[derive(Clone)]
+pub struct GameState {
+    pub(crate) game_over: bool,
+    pub(crate) game_terminated: bool,
+    pub(crate) current_score: u64,
+    pub(crate) tick_count: u64,
+    pub(crate) mutators: Vec<Mutator>,
+    pub(crate) mode: GameMode,
+    pub(crate) snakes: Vec<Snake>,
+    pub(crate) fruits: Vec<Fruit>,
+    /// number of fields the walls have moved inwards on each side
+    pub(crate) wall_inset: u16,
+    /// whether the player filled the board in classic mode, is the last snake alive in arena mode,
+    /// solved the level in puzzle mode or outlasted the opponent in tron mode
+    pub(crate) won: bool,
+    /// the level being played in puzzle mode
+    pub(crate) puzzle_level: Option<PuzzleLevel>,
+    /// the opponent of the player in tron mode
+    pub(crate) tron_opponent: TronOpponent,
+    /// determines the placement of the fruits, a game started from the same seed plays out the same
+    /// given the same moves
+    pub(crate) seed: u64,
+    pub(crate) rng: Rng,
+}
+
+impl GameState {
+    pub fn new(mode: GameMode, mutators: Vec<Mutator>) -> Self {
+        let seed = rand::random();
+        let mut game_state = GameState {
+            game_over: false,
+            game_terminated: false,
+            current_score: 0,
+            tick_count: 0,
+            mutators,
+            mode,
+            snakes: Vec::new(),
+            fruits: Vec::new(),
+            wall_inset: 0,
+            won: false,
+            puzzle_level: None,
+            tron_opponent: TronOpponent::NONE,
+            seed,
+            rng: Rng::new(seed),
+        };
+        game_state.reset();
+
+        game_state
+    }
+
+    pub fn new_puzzle(level: PuzzleLevel) -> Self {
+        let mut game_state = Self::new(GameMode::PUZZLE, Vec::new());
+        game_state.puzzle_level = Some(level);
+        game_state.reset();
+
+        game_state
+    }
+
+    pub fn new_tron(opponent: TronOpponent, mutators: Vec<Mutator>) -> Self {
+        let mut game_state = Self::new(GameMode::TRON, mutators);
+        game_state.tron_opponent = opponent;
+        game_state.reset();
+
+        game_state
+    }
+
+    /// Starts a new game from the provided seed
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.reset();
+    }
+
+    /// Resets everything but the game mode, mutators and seed to start a new game
+    pub fn reset(&mut self) {
+        self.rng = Rng::new(self.seed);
+        self.game_over = false;
+        self.current_score = 0;
+        self.tick_count = 0;
+        self.wall_inset = 0;
+        self.won = false;
+
+        match self.mode {
+            GameMode::CLASSIC => {
+                self.snakes = vec![Snake::new()];
+                let occupied = self.create_occupancy_matrix();
+                self.fruits = vec![Fruit::new(0, &occupied, &mut self.rng)];
+            }
+            GameMode::ARENA => {
+                self.snakes = arena::spawn_snakes();
+                let occupied = self.create_occupancy_matrix();
+                let rng = &mut self.rng;
+                self.fruits = (0..arena::FRUIT_COUNT).map(|_| Fruit::new(0, &occupied, rng)).collect();
+            }
+            GameMode::PUZZLE => {
+                self.snakes = vec![Snake::new()];
+                self.fruits = match &self.puzzle_level {
+                    Some(level) => level.fruits.iter().map(|(x_pos, y_pos)| Fruit { x_pos: *x_pos, y_pos: *y_pos }).collect(),
+                    None => Vec::new(),
+                };
+            }
+            GameMode::TRON => {
+                self.snakes = tron::spawn_snakes(self.tron_opponent);
+                self.fruits = Vec::new();
+            }
+        }
+    }
+
+    pub fn player(&self) -> &Snake {
+        &self.snakes[PLAYER_INDEX]
+    }
+
+    pub fn player_mut(&mut self) -> &mut Snake {
+        &mut self.snakes[PLAYER_INDEX]
+    }
+
+    /// Returns true if the provided position is part of the walls, taking the wall inset into account
+    pub fn is_wall(&self, x_pos: u16, y_pos: u16) -> bool {
+        x_pos <= self.wall_inset
+            || y_pos <= self.wall_inset
+            || x_pos >= FIELD_WIDTH - 1 - self.wall_inset
+            || y_pos >= FIELD_HEIGHT - 1 - self.wall_inset
+    }
+
+    /// Takes the snake out of the game because its controller broke the rules, the game is over if
+    /// it is the player's
+    pub fn disqualify(&mut self, snake_index: usize) {
+        self.snakes[snake_index].kill(DeathCause::Disqualified);
+        if snake_index == PLAYER_INDEX {
+            self.game_over = true;
+        }
+    }
+
+    /// Returns the number of fields the tail grows by per fruit eaten
+    pub fn get_growth_per_fruit(&self, ruleset: &Ruleset) -> u16 {
+        let growth_factor = if self.mutators.contains(&Mutator::DoubleGrowth) { 2 } else { 1 };
+        ruleset.get_growth_per_fruit() * growth_factor
+    }
+
+    /// Returns the score multiplier of the ruleset in percent, increased by the bonuses of the active
+    /// mutators which add up when stacked
+    pub fn get_score_multiplier(&self, ruleset: &Ruleset) -> u64 {
+        let bonus: u64 = self.mutators.iter().map(|mutator| mutator.get_score_bonus()).sum();
+        ruleset.get_score_multiplier() as u64 * (100 + bonus)
+    }
+
+    /// Returns the points awarded for a fruit
+    pub fn get_fruit_points(&self, ruleset: &Ruleset) -> u64 {
+        5 * self.get_score_multiplier(ruleset) / 100
+    }
+
+    /// Returns true once the player is long enough to cover every field inside the walls, which is
+    /// the perfect end of a classic game
+    pub fn is_board_full(&self) -> bool {
+        let inner_width = (FIELD_WIDTH - 2 - 2 * self.wall_inset) as usize;
+        let inner_height = (FIELD_HEIGHT - 2 - 2 * self.wall_inset) as usize;
+        self.player().tail_x_pos.len() + 1 >= inner_width * inner_height
+    }
+
+    /// Returns a matrix indexed by y and x that is true for every field occupied by a living snake
+    pub fn create_occupancy_matrix(&self) -> Vec<Vec<bool>> {
+        let mut matrix = vec![vec![false; FIELD_WIDTH as usize]; FIELD_HEIGHT as usize];
+
+        for snake in self.snakes.iter().filter(|snake| snake.alive) {
+            let positions = snake.tail_x_pos.iter().zip(snake.tail_y_pos.iter()).chain(std::iter::once((&snake.x_pos, &snake.y_pos)));
+            for (x_pos, y_pos) in positions {
+                // mind that newly created tail elements are spawned out of view
+                if *x_pos < FIELD_WIDTH && *y_pos < FIELD_HEIGHT {
+                    matrix[*y_pos as usize][*x_pos as usize] = true;
+                }
+            }
+        }
+
+        matrix
+    }
+
+    /// Advances the game by one tick: AI controlled snakes choose their direction, all living snakes
+    /// move, then collisions with walls, tails and other heads are resolved before snakes eat fruits
+    pub fn handle_snake_movement(&mut self, ruleset: &Ruleset) {
+        // the player pausing pauses the entire game
+        if self.player().direction == STOP {
+            return;
+        }
+
+        self.tick_count += 1;
+        for i in 0..self.snakes.len() {
+            if self.snakes[i].ai_controlled && self.snakes[i].alive {
+                self.snakes[i].direction = ai::choose_direction(self, i, ruleset);
+            }
+        }
+
+        for snake in self.snakes.iter_mut().filter(|snake| snake.alive) {
+            let direction = snake.direction;
+            // light cycles leave a permanent trail, so the tail grows by one element on each move
+            if self.mode == GameMode::TRON && direction != STOP {
+                snake.append_tail();
+            }
+            snake.move_pos(&direction);
+        }
+
+        // do not use an else if here since moving the snake when is_game_over_on_wall_collision is
+        // false might result in the snake being placed an a fruit, so this should always get checked
+        let mut killed: Vec<Option<DeathCause>> = vec![None; self.snakes.len()];
+        for (i, death_cause) in killed.iter_mut().enumerate() {
+            let (x_pos, y_pos) = (self.snakes[i].x_pos, self.snakes[i].y_pos);
+            if !self.snakes[i].alive || !self.is_wall(x_pos, y_pos) {
+                continue;
+            }
+
+            if ruleset.is_game_over_on_wall_collision() {
+                *death_cause = Some(DeathCause::Wall);
+            } else {
+                let wrapped = self.wrap_position(x_pos, y_pos);
+                self.snakes[i].x_pos = wrapped.0;
+                self.snakes[i].y_pos = wrapped.1;
+            }
+        }
+
+        for (i, death_cause) in killed.iter_mut().enumerate() {
+            let snake = &self.snakes[i];
+            if !snake.alive || death_cause.is_some() {
+                continue;
+            }
+
+            for (j, other) in self.snakes.iter().enumerate() {
+                if !other.alive {
+                    continue;
+                }
+
+                if i != j && other.x_pos == snake.x_pos && other.y_pos == snake.y_pos {
+                    *death_cause = Some(DeathCause::HeadOn);
+                } else if other.tail_contains(snake.x_pos, snake.y_pos) {
+                    *death_cause = Some(if i == j { DeathCause::OwnTail } else { DeathCause::OtherTail });
+                }
+            }
+        }
+
+        for (snake, death_cause) in self.snakes.iter_mut().zip(killed) {
+            if let Some(death_cause) = death_cause {
+                snake.kill(death_cause);
+            }
+        }
+
+        if self.mutators.contains(&Mutator::DriftingFruit) && self.tick_count.is_multiple_of(FRUIT_DRIFT_INTERVAL) {
+            // fruits drift onto free fields only, so they neither land on a snake nor on each other
+            let mut occupied = self.create_occupancy_matrix();
+            for fruit in self.fruits.iter() {
+                occupied[fruit.y_pos as usize][fruit.x_pos as usize] = true;
+            }
+            for fruit in self.fruits.iter_mut() {
+                occupied[fruit.y_pos as usize][fruit.x_pos as usize] = false;
+                fruit.drift(self.wall_inset, &occupied, &mut self.rng);
+                occupied[fruit.y_pos as usize][fruit.x_pos as usize] = true;
+            }
+        }
+
+        let growth = self.get_growth_per_fruit(ruleset);
+        let fruit_points = self.get_fruit_points(ruleset);
+        let mut eaten = vec![false; self.fruits.len()];
+        for i in 0..self.snakes.len() {
+            if !self.snakes[i].alive {
+                continue;
+            }
+
+            for (fruit, is_eaten) in self.fruits.iter().zip(eaten.iter_mut()) {
+                let snake = &mut self.snakes[i];
+                if snake.x_pos == fruit.x_pos && snake.y_pos == fruit.y_pos {
+                    if i == PLAYER_INDEX {
+                        self.current_score += fruit_points;
+                    }
+                    for _ in 0..growth {
+                        snake.append_tail();
+                    }
+                    *is_eaten = true;
+                }
+            }
+        }
+
+        // fruits respawn once all snakes have grown so they never land on a snake
+        if self.mode != GameMode::PUZZLE && eaten.contains(&true) {
+            let occupied = self.create_occupancy_matrix();
+            for (fruit, is_eaten) in self.fruits.iter_mut().zip(eaten) {
+                if is_eaten {
+                    fruit.respawn(self.wall_inset, &occupied, &mut self.rng);
+                }
+            }
+        }
+
+        // fruits of puzzle levels have fixed placements and are not replaced once eaten
+        if self.mode == GameMode::PUZZLE {
+            let (head_x, head_y) = (self.player().x_pos, self.player().y_pos);
+            self.fruits.retain(|fruit| fruit.x_pos != head_x || fruit.y_pos != head_y);
+        }
+
+        match self.mode {
+            GameMode::ARENA => arena::handle_arena_tick(self, ruleset),
+            GameMode::PUZZLE => self.handle_puzzle_move(),
+            GameMode::TRON => tron::handle_tron_tick(self, ruleset),
+            GameMode::CLASSIC => {
+                self.won = self.player().alive && self.is_board_full();
+                self.game_over = !self.player().alive || self.won;
+            }
+        }
+    }
+
+    /// Ends a puzzle game once all fruits have been eaten or the move budget is used up
+    fn handle_puzzle_move(&mut self) {
+        let move_budget = self.puzzle_level.as_ref().map(|level| level.move_budget).unwrap_or(0);
+
+        if !self.player().alive {
+            self.game_over = true;
+        } else if self.fruits.is_empty() {
+            self.won = true;
+            self.current_score += UNUSED_MOVE_BONUS * (move_budget - self.tick_count);
+            self.game_over = true;
+        } else if self.tick_count >= move_budget {
+            self.game_over = true;
+        }
+    }
+
+    /// Moves a position inside the walls to the opposite side of the field
+    pub(crate) fn wrap_position(&self, mut x_pos: u16, mut y_pos: u16) -> (u16, u16) {
+        let min_index = self.wall_inset;
+        let max_x_index = FIELD_WIDTH - 1 - self.wall_inset;
+        let max_y_index = FIELD_HEIGHT - 1 - self.wall_inset;
+
+        if x_pos <= min_index {
+            x_pos = max_x_index - 1;
+        } else if x_pos >= max_x_index {
+            x_pos = min_index + 1;
+        }
+        if y_pos <= min_index {
+            y_pos = max_y_index - 1;
+        } else if y_pos >= max_y_index {
+            y_pos = min_index + 1;
+        }
+
+        (x_pos, y_pos)
+    }
+}
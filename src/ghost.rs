@@ -0,0 +1,89 @@
+use pancurses::{chtype, Window, A_DIM};
+
+use crate::{FIELD_HEIGHT, FIELD_WIDTH, HEAD_SYMBOL, TAIL_SYMBOL};
+use crate::direction::Direction;
+use crate::game_state::GameState;
+use crate::mutator::Mutator;
+use crate::replay::Replay;
+use crate::ruleset::Ruleset;
+use crate::score_manager::ScoreManager;
+
+/// The best earlier run of the player, played back next to the current game as a dimmed snake to
+/// race against. Both games start from the same seed, so the fruits show up in the same places.
+pub struct Ghost {
+    replay: Replay,
+    game_state: GameState,
+    /// number of recorded ticks played so far
+    tick: usize,
+}
+
+impl Ghost {
+    /// Returns the ghost of the user's highest verified score on the leaderboard whose replay was
+    /// played with the same setup as the game, only runs from the seed count if one is provided
+    pub fn find_personal_best(score_manager: &ScoreManager, leaderboard_id: &str, user: &str, game_state: &GameState, ruleset: &Ruleset, seed: Option<u64>) -> Option<Ghost> {
+        let mut scores: Vec<_> = score_manager.get_scores(Some(leaderboard_id)).into_iter()
+            .filter(|score| score.verified && score.user == user)
+            .collect();
+        scores.sort_by_key(|score| score.score);
+
+        scores.iter().rev()
+            .filter_map(|score| Replay::read(score.replay.as_ref()?).ok())
+            .find(|replay| {
+                replay.mode == game_state.mode
+                    && replay.tron_opponent == game_state.tron_opponent
+                    && replay.puzzle_level.as_deref() == game_state.puzzle_level.as_ref().map(|level| level.id)
+                    && replay.ruleset.id == ruleset.id
+                    && replay.ruleset.has_same_rules(ruleset)
+                    && replay.mutators == game_state.mutators
+                    && seed.is_none_or(|seed| replay.seed == seed)
+            })
+            .map(|replay| Ghost { game_state: replay.create_game_state(), replay, tick: 0 })
+    }
+
+    /// Returns the seed the game has to be started from to race the ghost
+    pub fn get_seed(&self) -> u64 {
+        self.replay.seed
+    }
+
+    /// Advances the ghost along with the game, call it right before the game advances. Like
+    /// recording, the ghost waits while the player pauses.
+    pub fn advance(&mut self, game_state: &GameState) {
+        if game_state.player().direction == Direction::STOP || self.tick == self.replay.inputs.len() {
+            return;
+        }
+
+        self.replay.play_tick(&mut self.game_state, self.tick);
+        self.tick += 1;
+    }
+
+    /// Draws the snake of the ghost dimmed onto the empty fields of the drawn field, it does not
+    /// hide anything of the actual game
+    pub fn draw(&self, window: &Window) {
+        let snake = self.game_state.player();
+        if !snake.alive || self.game_state.game_over {
+            return;
+        }
+
+        let mut fields = vec![(snake.x_pos, snake.y_pos, HEAD_SYMBOL)];
+        if !self.game_state.mutators.contains(&Mutator::InvisibleTail) {
+            fields.extend(snake.tail_x_pos.iter().zip(snake.tail_y_pos.iter()).map(|(x, y)| (*x, *y, TAIL_SYMBOL)));
+        }
+        // newly created tail elements are spawned out of view, below the field on the HUD
+        for (x, y, symbol) in fields.into_iter().filter(|(x, y, _)| *x < FIELD_WIDTH && *y < FIELD_HEIGHT) {
+            // the lowest byte holds the character
+            if window.mvinch(y as i32, x as i32) as u8 == b' ' {
+                window.mvaddch(y as i32, x as i32, symbol as chtype | A_DIM);
+            }
+        }
+    }
+
+    /// Returns the HUD line comparing the score of the game against the ghost at the same tick
+    pub fn get_score_delta_display(&self, game_state: &GameState) -> String {
+        let delta = game_state.current_score as i64 - self.game_state.current_score as i64;
+        if self.game_state.game_over {
+            format!("Ghost:                                  {:+} (ghost finished with {})", delta, self.game_state.current_score)
+        } else {
+            format!("Ghost:                                  {:+} (ghost {})", delta, self.game_state.current_score)
+        }
+    }
+}
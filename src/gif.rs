@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/// Largest number of codes the LZW compression of GIF supports
+const MAX_CODE_COUNT: u16 = 4096;
+
+/// Writes an animated GIF that loops forever, frame by frame. All frames share one palette of at
+/// most 256 colors and are drawn over the previous one, so a frame only needs to cover the pixels
+/// that changed.
+pub struct GifWriter {
+    file: BufWriter<File>,
+    /// number of bits of a palette index, at least 2 as LZW requires
+    index_bits: u8,
+}
+
+impl GifWriter {
+    /// Creates the file and writes the header, the palette holds the colors as RGB
+    pub fn create(file_path: &str, width: u16, height: u16, palette: &[[u8; 3]]) -> io::Result<Self> {
+        let mut index_bits = 2;
+        while (1 << index_bits) < palette.len() {
+            index_bits += 1;
+        }
+        if index_bits > 8 {
+            return Err(io::Error::other("a palette holds at most 256 colors"));
+        }
+
+        let mut file = BufWriter::new(File::create(file_path)?);
+        file.write_all(b"GIF89a")?;
+        file.write_all(&width.to_le_bytes())?;
+        file.write_all(&height.to_le_bytes())?;
+        // global palette present, 8 bits per primary color, size of the palette, background color
+        // and aspect ratio
+        file.write_all(&[0xF0 | (index_bits - 1), 0, 0])?;
+        for i in 0..1 << index_bits {
+            file.write_all(palette.get(i).unwrap_or(&[0, 0, 0]))?;
+        }
+        // application extension repeating the animation forever
+        file.write_all(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+
+        Ok(GifWriter { file, index_bits })
+    }
+
+    /// Adds a frame covering the rectangle at the position, the pixels are palette indices row by
+    /// row and the delay until the next frame is in hundredths of a second
+    pub fn add_frame(&mut self, x: u16, y: u16, width: u16, height: u16, pixels: &[u8], delay: u16) -> io::Result<()> {
+        // graphic control extension: keep the frame when drawing the next one
+        self.file.write_all(&[0x21, 0xF9, 0x04, 0x04])?;
+        self.file.write_all(&delay.to_le_bytes())?;
+        self.file.write_all(&[0, 0])?;
+
+        self.file.write_all(&[0x2C])?;
+        for value in [x, y, width, height] {
+            self.file.write_all(&value.to_le_bytes())?;
+        }
+        self.file.write_all(&[0, self.index_bits])?;
+
+        // the compressed data is split into blocks of at most 255 bytes, an empty block ends it
+        for block in compress(pixels, self.index_bits).chunks(255) {
+            self.file.write_all(&[block.len() as u8])?;
+            self.file.write_all(block)?;
+        }
+        self.file.write_all(&[0])
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.file.write_all(&[0x3B])?;
+        self.file.flush()
+    }
+}
+
+/// Compresses the pixels with the variable code length LZW of GIF
+fn compress(pixels: &[u8], index_bits: u8) -> Vec<u8> {
+    let clear_code: u16 = 1 << index_bits;
+    let end_code = clear_code + 1;
+    let mut output = BitWriter { bytes: Vec::new(), buffer: 0, bit_count: 0 };
+    let mut code_size = index_bits + 1;
+    let mut next_code = end_code + 1;
+    let mut codes: HashMap<(u16, u8), u16> = HashMap::new();
+
+    output.write(clear_code, code_size);
+    let mut pixels = pixels.iter();
+    let mut prefix = match pixels.next() {
+        Some(pixel) => *pixel as u16,
+        None => {
+            output.write(end_code, code_size);
+            return output.finish();
+        }
+    };
+
+    for pixel in pixels {
+        if let Some(code) = codes.get(&(prefix, *pixel)) {
+            prefix = *code;
+            continue;
+        }
+
+        output.write(prefix, code_size);
+        if next_code == MAX_CODE_COUNT {
+            // the table is full, start over with the single pixel codes
+            output.write(clear_code, code_size);
+            codes.clear();
+            code_size = index_bits + 1;
+            next_code = end_code + 1;
+        } else {
+            // the decoder widens its codes once it has assigned the last code of the current width
+            if next_code >= 1 << code_size {
+                code_size += 1;
+            }
+            codes.insert((prefix, *pixel), next_code);
+            next_code += 1;
+        }
+        prefix = *pixel as u16;
+    }
+    output.write(prefix, code_size);
+    // the decoder assigns a code for the last prefix as well and may widen its codes before the end
+    if next_code < MAX_CODE_COUNT && next_code >= 1 << code_size {
+        code_size += 1;
+    }
+    output.write(end_code, code_size);
+
+    output.finish()
+}
+
+/// Packs codes into bytes starting with the lowest bit
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bit_count: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bit_count;
+        self.bit_count += size;
+        while self.bit_count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bit_count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compressed_pixels_decompress_to_the_same_pixels() {
+        // four colors in short runs, the lengths cover every code width and the table filling up
+        let mut seed: u32 = 1;
+        let mut pixels = Vec::new();
+        for length in 0..40_000 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            if seed >> 16 & 1 == 0 || pixels.is_empty() {
+                pixels.push((seed >> 24) as u8 & 3);
+            } else {
+                pixels.push(pixels[pixels.len() - 1]);
+            }
+            if length < 600 || length % 397 == 0 {
+                assert_eq!(decompress(&compress(&pixels, 2), 2), pixels, "{} pixels", pixels.len());
+            }
+        }
+    }
+
+    /// Decodes the variable code length LZW of GIF
+    fn decompress(bytes: &[u8], index_bits: u8) -> Vec<u8> {
+        let clear_code = 1 << index_bits;
+        let end_code = clear_code + 1;
+        let single_pixel_codes = || (0..=end_code).map(|code| vec![code as u8]).collect::<Vec<Vec<u8>>>();
+        let mut codes = single_pixel_codes();
+        let mut code_size = index_bits + 1;
+        let mut previous: Option<Vec<u8>> = None;
+        let mut pixels = Vec::new();
+        let mut position = 0;
+
+        loop {
+            let code = (0..code_size as usize)
+                .map(|i| ((bytes[(position + i) / 8] >> ((position + i) % 8)) & 1) as usize)
+                .enumerate()
+                .fold(0, |code, (i, bit)| code | bit << i);
+            position += code_size as usize;
+
+            if code == clear_code {
+                codes = single_pixel_codes();
+                code_size = index_bits + 1;
+                previous = None;
+                continue;
+            }
+            if code == end_code {
+                return pixels;
+            }
+
+            let entry = match (codes.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) if code == codes.len() => [previous.clone(), vec![previous[0]]].concat(),
+                _ => panic!("code {} is not in the table of {} codes", code, codes.len()),
+            };
+            pixels.extend(&entry);
+            if let Some(previous) = previous {
+                if codes.len() < MAX_CODE_COUNT as usize {
+                    codes.push([previous, vec![entry[0]]].concat());
+                }
+            }
+            if codes.len() == 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+            previous = Some(entry);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{ai, FIELD_HEIGHT, FIELD_WIDTH};
+use crate::autopilot::AutopilotController;
+use crate::controller::{Controller, Observation};
+use crate::direction::Direction;
+use crate::game_mode::GameMode;
+
+/// Shortcuts are only taken while the snake covers less than this share of the field in percent,
+/// longer snakes strictly follow the cycle
+const SHORTCUT_LENGTH_LIMIT: usize = 50;
+/// Number of free fields kept between the head and the end of the tail when taking a shortcut
+const SHORTCUT_SAFETY_MARGIN: usize = 4;
+
+/// Steers the snake along a cycle visiting every field inside the walls exactly once. The snake
+/// always occupies a stretch of the cycle behind its head, so following the cycle can never run into
+/// the tail and eventually fills the whole board. While the snake is short it skips ahead on the cycle
+/// towards the fruit as long as it stays clear of the end of its tail.
+///
+/// The cycle only covers the classic field, in other modes the autopilot takes over.
+pub struct HamiltonianController {
+    /// position of each field on the cycle, indexed by y and x
+    order: Vec<Vec<usize>>,
+    length: usize,
+    fallback: AutopilotController,
+}
+
+impl HamiltonianController {
+    /// Builds the cycle: the top row is traversed from left to right, then the remaining rows snake
+    /// back and forth leaving out the leftmost column, which leads back up to the start. This only
+    /// works for an even number of rows inside the walls.
+    pub fn new() -> Self {
+        let (min, max_x, max_y) = (1, FIELD_WIDTH - 2, FIELD_HEIGHT - 2);
+        assert!((max_y - min + 1).is_multiple_of(2), "the hamiltonian cycle requires an even number of rows");
+
+        let mut fields: Vec<(u16, u16)> = (min..=max_x).map(|x_pos| (x_pos, min)).collect();
+        for y_pos in min + 1..=max_y {
+            if (y_pos - min).is_multiple_of(2) {
+                fields.extend((min + 1..=max_x).map(|x_pos| (x_pos, y_pos)));
+            } else {
+                fields.extend((min + 1..=max_x).rev().map(|x_pos| (x_pos, y_pos)));
+            }
+        }
+        fields.extend((min + 1..=max_y).rev().map(|y_pos| (min, y_pos)));
+
+        let mut order = vec![vec![0; FIELD_WIDTH as usize]; FIELD_HEIGHT as usize];
+        for (i, (x_pos, y_pos)) in fields.iter().enumerate() {
+            order[*y_pos as usize][*x_pos as usize] = i;
+        }
+
+        HamiltonianController {
+            order,
+            length: fields.len(),
+            fallback: AutopilotController,
+        }
+    }
+
+    fn position(&self, pos: (u16, u16)) -> usize {
+        self.order[pos.1 as usize][pos.0 as usize]
+    }
+
+    /// Returns the number of steps along the cycle from a to b
+    fn cycle_distance(&self, a: (u16, u16), b: (u16, u16)) -> usize {
+        (self.position(b) + self.length - self.position(a)) % self.length
+    }
+}
+
+impl Controller for HamiltonianController {
+    fn next_direction(&mut self, observation: &Observation) -> Direction {
+        let game_state = observation.game_state();
+        let ruleset = observation.ruleset();
+        if game_state.mode != GameMode::CLASSIC || game_state.wall_inset != 0 || game_state.snakes.len() != 1 {
+            return self.fallback.next_direction(observation);
+        }
+
+        let snake = observation.snake();
+        let head = (snake.x_pos, snake.y_pos);
+        let snake_length = snake.tail_x_pos.len() + 1;
+        // tail elements yet to enter the field are at the end of the tail
+        let pending_growth = snake.tail_x_pos.iter().rev().take_while(|x_pos| **x_pos >= FIELD_WIDTH).count();
+
+        // number of fields ahead of the head before the end of the tail is reached, the snake lies
+        // behind its head on the cycle so all of them are free
+        let free_fields = match snake.tail_x_pos.len() - pending_growth {
+            0 => self.length,
+            tail_end => self.cycle_distance(head, (snake.tail_x_pos[tail_end - 1], snake.tail_y_pos[tail_end - 1])),
+        };
+        let fruit_distance = game_state.fruits.iter()
+            .map(|fruit| self.cycle_distance(head, (fruit.x_pos, fruit.y_pos)))
+            .min()
+            .unwrap_or(self.length);
+
+        // the end of the tail stays in place while the snake grows, which happens for the elements
+        // yet to enter the field and again once the fruit is eaten
+        let max_skip = if snake_length * 100 < self.length * SHORTCUT_LENGTH_LIMIT {
+            free_fields.saturating_sub(pending_growth + game_state.get_growth_per_fruit(ruleset) as usize + SHORTCUT_SAFETY_MARGIN)
+        } else {
+            0
+        };
+
+        let mut best: Option<(Direction, usize)> = None;
+        for direction in [Direction::UP, Direction::DOWN, Direction::LEFT, Direction::RIGHT].iter() {
+            let next_pos = match ai::next_position(game_state, head.0, head.1, direction, ruleset) {
+                Some(pos) => pos,
+                None => continue
+            };
+
+            // the next field on the cycle is always allowed unless it is the end of a growing tail,
+            // skipping ahead must neither come close to the end of the tail nor pass the fruit
+            let skip = self.cycle_distance(head, next_pos);
+            let is_free = skip < free_fields || (skip == free_fields && pending_growth == 0);
+            let allowed = is_free && (skip == 1 || (skip <= max_skip && skip <= fruit_distance));
+            if allowed && best.is_none_or(|(_, best_skip)| skip > best_skip) {
+                best = Some((*direction, skip));
+            }
+        }
+
+        match best {
+            Some((direction, _)) => direction,
+            None => self.fallback.next_direction(observation)
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use crate::autopilot;
+use crate::game_mode::GameMode;
+use crate::game_state::{GameState, PLAYER_INDEX};
+use crate::ruleset::Ruleset;
+
+/// Number of upcoming moves highlighted by a hint
+pub const HINT_LENGTH: usize = 5;
+
+/// Advice for the player on the current state of the game, shown as an overlay when hints are enabled
+pub struct Hint {
+    /// the next fields along the shortest path to a fruit after which the snake can still reach the
+    /// end of its tail, empty if there is no such path
+    pub(crate) path: Vec<(u16, u16)>,
+    /// whether the current heading leads into a pocket the snake cannot get out of
+    pub(crate) dead_end_ahead: bool,
+}
+
+impl Hint {
+    /// Solves the current state for the player. Light cycles have neither fruits to reach nor a tail
+    /// that frees up fields, so there are no hints in tron mode.
+    pub fn for_player(game_state: &GameState, ruleset: &Ruleset) -> Option<Hint> {
+        if game_state.mode == GameMode::TRON || !game_state.player().alive {
+            return None;
+        }
+
+        let path = autopilot::find_safe_fruit_path(game_state, ruleset, PLAYER_INDEX)
+            .map(|path| path.iter().take(HINT_LENGTH).map(|(pos, _)| *pos).collect())
+            .unwrap_or_default();
+
+        Some(Hint {
+            path,
+            dead_end_ahead: autopilot::is_dead_end(game_state, ruleset, PLAYER_INDEX, game_state.player().direction),
+        })
+    }
+
+    pub(crate) fn get_advice(&self) -> &str {
+        if self.dead_end_ahead {
+            "DEAD END AHEAD - turn away!"
+        } else if self.path.is_empty() {
+            "no safe path to a fruit, follow your tail"
+        } else {
+            "follow the dots to the fruit"
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::process;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use pancurses::{endwin, Input, Window};
+
+/// Number of the signal that asked the game to end, 0 while there is none
+static RECEIVED_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn handle_signal(signal: libc::c_int) {
+    RECEIVED_SIGNAL.store(signal, Ordering::SeqCst);
+}
+
+/// Catches Ctrl-C and SIGTERM instead of being killed by them, so a game in progress can be recorded
+/// and the terminal restored before exiting. Install it before curses is started, curses only sets
+/// up handlers for signals that are not handled yet.
+#[cfg(unix)]
+pub fn install() {
+    for signal in [libc::SIGINT, libc::SIGTERM] {
+        // without SA_RESTART a blocking read for a key returns once the signal arrives, so the game
+        // notices it without waiting for the next key
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handle_signal as *const () as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(signal, &action, std::ptr::null_mut());
+        }
+    }
+}
+
+#[cfg(windows)]
+pub fn install() {
+    for signal in [libc::SIGINT, libc::SIGTERM] {
+        unsafe {
+            libc::signal(signal, handle_signal as *const () as libc::sighandler_t);
+        }
+    }
+}
+
+/// Returns the signal received since the handlers were installed, if any
+pub fn get_received_signal() -> Option<i32> {
+    match RECEIVED_SIGNAL.load(Ordering::SeqCst) {
+        0 => None,
+        signal => Some(signal),
+    }
+}
+
+/// Returns the exit status of a process ended by the signal, as reported by shells
+pub fn get_exit_code(signal: i32) -> i32 {
+    128 + signal
+}
+
+/// Reads a key outside of a game, exits right away if a signal arrived in the meantime since there
+/// is nothing to record
+pub fn read_key(window: &Window) -> Option<Input> {
+    let input = window.getch();
+    if let Some(signal) = get_received_signal() {
+        endwin();
+        process::exit(get_exit_code(signal));
+    }
+
+    input
+}
@@ -0,0 +1,117 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+
+use pancurses::{Input, Window};
+
+use crate::controller::{Controller, Observation};
+use crate::direction::Direction;
+use crate::game_mode::GameMode;
+use crate::game_state::PLAYER_INDEX;
+use crate::mutator::Mutator;
+
+const PAUSE_KEY: char = 'p';
+const HINT_KEY: char = 'h';
+
+/// Keys steering a snake, the first player may also pause the game
+#[derive(Clone, Copy)]
+pub struct KeyLayout {
+    up: char,
+    down: char,
+    left: char,
+    right: char,
+    pause: Option<char>,
+}
+
+pub const FIRST_PLAYER_KEYS: KeyLayout = KeyLayout { up: 'w', down: 's', left: 'a', right: 'd', pause: Some(PAUSE_KEY) };
+pub const SECOND_PLAYER_KEYS: KeyLayout = KeyLayout { up: 'i', down: 'k', left: 'j', right: 'l', pause: None };
+
+impl KeyLayout {
+    fn get_direction(&self, input_char: char) -> Option<Direction> {
+        match input_char {
+            c if c == self.up => Some(Direction::UP),
+            c if c == self.down => Some(Direction::DOWN),
+            c if c == self.left => Some(Direction::LEFT),
+            c if c == self.right => Some(Direction::RIGHT),
+            c if Some(c) == self.pause => Some(Direction::STOP),
+            _ => None
+        }
+    }
+}
+
+/// Keys read from the window that have not been consumed yet. Both players of a local versus game
+/// share the keyboard, so keys belonging to the other player are kept until it is their turn.
+pub struct KeyboardInput<'a> {
+    window: &'a Window,
+    pending: RefCell<VecDeque<char>>,
+    /// toggled by the hint key whenever it is read
+    hints_enabled: Cell<bool>,
+}
+
+impl<'a> KeyboardInput<'a> {
+    pub fn new(window: &'a Window) -> Self {
+        KeyboardInput {
+            window,
+            pending: RefCell::new(VecDeque::new()),
+            hints_enabled: Cell::new(false),
+        }
+    }
+
+    /// Returns whether the player asked for hints, in turn-based games toggling them takes effect
+    /// with the next move
+    pub fn shows_hints(&self) -> bool {
+        self.hints_enabled.get()
+    }
+
+    /// Returns the direction of the oldest pending key of the layout. Waits for such a key if blocking,
+    /// otherwise returns None once there are no more keys to read.
+    fn next_direction(&self, layout: &KeyLayout, blocking: bool) -> Option<Direction> {
+        let mut pending = self.pending.borrow_mut();
+        loop {
+            if let Some(index) = pending.iter().position(|c| layout.get_direction(*c).is_some()) {
+                let direction = pending.remove(index).and_then(|c| layout.get_direction(c));
+                // the pause key does not make a move in turn-based games
+                if !(blocking && direction == Some(Direction::STOP)) {
+                    return direction;
+                }
+                continue;
+            }
+
+            match self.window.getch() {
+                Some(Input::Character(input_char)) if is_steering_key(input_char) => pending.push_back(input_char),
+                Some(Input::Character(HINT_KEY)) => self.hints_enabled.set(!self.hints_enabled.get()),
+                Some(_) => {}
+                None => return None
+            }
+        }
+    }
+}
+
+fn is_steering_key(input_char: char) -> bool {
+    FIRST_PLAYER_KEYS.get_direction(input_char).is_some() || SECOND_PLAYER_KEYS.get_direction(input_char).is_some()
+}
+
+/// Steers a snake with the keys of the layout, processing one key per tick. Turn-based games wait for
+/// a key instead of keeping the current direction.
+pub struct KeyboardController<'a> {
+    input: &'a KeyboardInput<'a>,
+    layout: KeyLayout,
+}
+
+impl<'a> KeyboardController<'a> {
+    pub fn new(input: &'a KeyboardInput<'a>, layout: KeyLayout) -> Self {
+        KeyboardController { input, layout }
+    }
+}
+
+impl Controller for KeyboardController<'_> {
+    fn next_direction(&mut self, observation: &Observation) -> Direction {
+        let game_state = observation.game_state();
+        let blocking = game_state.mode == GameMode::PUZZLE;
+        match self.input.next_direction(&self.layout, blocking) {
+            // mutators only apply to the player, the current direction has already been inverted
+            Some(direction) if observation.snake_index() == PLAYER_INDEX => Mutator::apply_to_direction(&game_state.mutators, direction),
+            Some(direction) => direction,
+            None => observation.snake().direction
+        }
+    }
+}
@@ -1,60 +1,97 @@
 use core::fmt;
-use std::{io, thread};
-use std::convert::TryInto;
+use std::{env, io, process, thread};
 use std::ops::Add;
 use std::time::Duration;
 
 use pancurses::*;
 use stopwatch::Stopwatch;
 
-use crate::difficulty::Difficulty;
+use crate::autopilot::AutopilotController;
+use crate::cli::Command;
+use crate::controller::{Controller, Observation};
+use crate::dataset::{Dataset, DatasetRecorder};
 use crate::direction::Direction;
 use crate::direction::Direction::STOP;
 use crate::duration_formatter::DurationFormatter;
-use crate::fruit::Fruit;
-use crate::score_manager::ScoreManager;
-use crate::snake::Snake;
-
-mod difficulty;
+use crate::game_mode::GameMode;
+use crate::game_state::{GameState, PLAYER_INDEX};
+use crate::ghost::Ghost;
+use crate::hint::Hint;
+use crate::keyboard::{FIRST_PLAYER_KEYS, KeyboardController, KeyboardInput, SECOND_PLAYER_KEYS};
+use crate::mutator::{FOG_OF_WAR_RADIUS, Mutator};
+use crate::puzzle::PuzzleLevel;
+use crate::replay::Replay;
+use crate::ruleset::Ruleset;
+use crate::save_game::{AUTOSAVE_INTERVAL, SaveGame};
+use crate::score_manager::{Score, ScoreManager};
+use crate::share_code::{Challenge, ShareCode};
+use crate::splits::Splits;
+use crate::tron::{OPPONENT_INDEX, TronOpponent};
+
+mod ai;
+mod animation;
+mod arena;
+mod asciicast;
+mod audit;
+mod autopilot;
+mod bench;
+mod cli;
+mod controller;
+mod dataset;
+mod death_cause;
 mod direction;
 mod duration_formatter;
+mod external;
 mod fruit;
+mod game_mode;
+mod game_state;
+mod ghost;
+mod gif;
+mod hamiltonian;
+mod hint;
+mod interrupt;
+mod keyboard;
+mod mutator;
+mod npz;
+mod puzzle;
+mod rating_manager;
+mod replay;
+mod replay_viewer;
+mod rng;
+mod ruleset;
+mod save_game;
 mod score_manager;
+mod share_code;
 mod snake;
+mod snake_env;
+mod splits;
+mod tournament;
+mod tron;
+mod wasm;
 
 const WALL_SYMBOL: char = '#';
 const FRUIT_SYMBOL: char = 'F';
 const HEAD_SYMBOL: char = 'O';
 const TAIL_SYMBOL: char = 'o';
+const RIVAL_HEAD_SYMBOL: char = '@';
+const RIVAL_TAIL_SYMBOL: char = 'x';
+const HINT_SYMBOL: char = '.';
 
 const FIELD_WIDTH: u16 = 50;
 const FIELD_HEIGHT: u16 = 50;
+/// Size of the terminal the field and the HUD are drawn in
+const WINDOW_COLS: u16 = FIELD_WIDTH + 20;
+const WINDOW_ROWS: u16 = FIELD_HEIGHT + 20;
 
-const UP_KEY: char = 'w';
-const DOWN_KEY: char = 's';
-const LEFT_KEY: char = 'a';
-const RIGHT_KEY: char = 'd';
-const PAUSE_KEY: char = 'p';
 const RETRY_KEY: char = 'r';
 const QUIT_KEY: char = 'q';
+const RESUME_KEY: char = 'r';
+const CONFIRM_KEY: char = '\n';
 
-struct GameState {
-    game_over: bool,
-    game_terminated: bool,
-    current_direction: Direction,
-    current_score: u64,
-}
-
-impl GameState {
-    fn new() -> Self {
-        GameState {
-            game_over: false,
-            game_terminated: false,
-            current_direction: Direction::STOP,
-            current_score: 0,
-        }
-    }
-}
+/// Ruleset of the demo shown on the title screen
+const ATTRACT_MODE_RULESET: &str = "ARCADE";
+/// Delay between the moves of a bot in turn-based games
+const BOT_MOVE_DELAY: u64 = 150;
 
 struct Cleanup;
 
@@ -65,6 +102,93 @@ impl Drop for Cleanup {
 }
 
 fn main() {
+    let command = Command::parse(env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, cli::USAGE);
+        process::exit(2);
+    });
+    let options = match command {
+        Command::Play(options) => options,
+        Command::BenchBot(options) => {
+            let ruleset = load_ruleset(&options.ruleset_id);
+            bench::run(&options, &ruleset);
+            return;
+        }
+        Command::BenchEnv(options) => {
+            let ruleset = load_ruleset(&options.ruleset_id);
+            snake_env::run_benchmark(&options, &ruleset);
+            return;
+        }
+        Command::Tournament(options) => {
+            let ruleset = load_ruleset(&options.ruleset_id);
+            tournament::run(&options, &ruleset);
+            return;
+        }
+        Command::Dataset(options) => {
+            let ruleset = load_ruleset(&options.ruleset_id);
+            dataset::run(&options, &ruleset);
+            return;
+        }
+        Command::Replay(options) => {
+            let replay = options.read_replay().unwrap_or_else(|message| {
+                eprintln!("{}", message);
+                process::exit(1);
+            });
+            play_replay(&replay);
+            return;
+        }
+        Command::ShareReplay(options) => {
+            let replay = options.read_replay().unwrap_or_else(|message| {
+                eprintln!("{}", message);
+                process::exit(1);
+            });
+            println!("replay code:\n{}\n", ShareCode::Replay(replay.clone()).encode());
+            println!("challenge code (seed and ruleset only):\n{}", ShareCode::Challenge(Challenge::from_replay(&replay)).encode());
+            return;
+        }
+        Command::ExportReplay(options) => {
+            let replay = Replay::read(&options.file).unwrap_or_else(|message| {
+                eprintln!("{}", message);
+                process::exit(1);
+            });
+            if let Some(file_path) = &options.asciicast {
+                asciicast::export(&replay, file_path).unwrap_or_else(|message| {
+                    eprintln!("{}", message);
+                    process::exit(1);
+                });
+                println!("{} ticks written to {}", replay.inputs.len(), file_path);
+            }
+            if let Some(file_path) = &options.svg {
+                animation::export_svg(&replay, file_path, &options).unwrap_or_else(|message| {
+                    eprintln!("{}", message);
+                    process::exit(1);
+                });
+                println!("{} ticks written to {}", replay.inputs.len(), file_path);
+            }
+            if let Some(file_path) = &options.gif {
+                animation::export_gif(&replay, file_path, &options).unwrap_or_else(|message| {
+                    eprintln!("{}", message);
+                    process::exit(1);
+                });
+                println!("{} ticks written to {}", replay.inputs.len(), file_path);
+            }
+            return;
+        }
+        Command::AuditScores => {
+            audit::run(&ScoreManager::from_file("scores.xml"), &Ruleset::load_all("rulesets.xml"));
+            return;
+        }
+    };
+    let challenge = options.challenge.as_ref().map(|code| {
+        ShareCode::decode(code)
+            .map(ShareCode::into_challenge)
+            .and_then(|challenge| Ok((challenge.find_ruleset(&Ruleset::load_all("rulesets.xml"))?, challenge)))
+            .unwrap_or_else(|message| {
+                eprintln!("{}", message);
+                process::exit(1);
+            })
+    });
+    // retries start from the seed of the challenge as well
+    let seed = challenge.as_ref().map(|(_, challenge)| challenge.seed).or(options.seed);
     // make sure endwin() is called even on panic
     let _cleanup = Cleanup;
     let score_manager = ScoreManager::from_file("scores.xml");
@@ -75,51 +199,227 @@ fn main() {
     io::stdin().read_line(&mut user_name_temp).expect("could not read user name");
     let user_name = user_name_temp.trim();
 
-    let window = initscr();
-    noecho();
-    let rows = FIELD_HEIGHT as i32 + 20;
-    let cols = FIELD_WIDTH as i32 + 20;
-    if window.get_max_x() < cols || window.get_max_y() < rows {
-        resize_term(rows, cols);
-    }
-
-    let difficulty = select_difficulty(&window);
-    window.nodelay(true);
-    let mut snake = Snake::new();
-    let mut fruit = Fruit::new();
+    interrupt::install();
+    let window = init_window();
+    run_attract_mode(&window);
+    let uses_keyboard = options.controller == controller::KEYBOARD_CONTROLLER;
+    // only games played with the keyboard are saved, challenges always start a new game
+    let save_game = if uses_keyboard && challenge.is_none() { offer_save_game(&window, user_name) } else { None };
+    let mode = match (&save_game, &challenge) {
+        (Some(save_game), _) => save_game.game_state.mode,
+        (None, Some((_, challenge))) => challenge.mode,
+        (None, None) => select_mode(&window),
+    };
+    let (ruleset, mut game_state) = if let Some(save_game) = &save_game {
+        (save_game.replay.ruleset.clone(), save_game.game_state.clone())
+    } else if let Some((ruleset, challenge)) = challenge {
+        (ruleset, challenge.create_game_state())
+    } else if mode == GameMode::PUZZLE {
+        let level = select_puzzle_level(&window);
+        (level.to_ruleset(), GameState::new_puzzle(level.clone()))
+    } else if mode == GameMode::TRON {
+        let opponent = select_tron_opponent(&window);
+        let rulesets = Ruleset::load_all("rulesets.xml");
+        let ruleset = select_ruleset(&window, &rulesets).clone();
+        let mutators = select_mutators(&window, &Mutator::all_for_mode(mode));
+        (ruleset, GameState::new_tron(opponent, mutators))
+    } else {
+        let rulesets = Ruleset::load_all("rulesets.xml");
+        let ruleset = select_ruleset(&window, &rulesets).clone();
+        let mutators = select_mutators(&window, &Mutator::all_for_mode(mode));
+        (ruleset, GameState::new(mode, mutators))
+    };
+    let ruleset = &ruleset;
+    // puzzle mode is turn-based, so it waits for input instead of advancing on a timer
+    let is_turn_based = mode == GameMode::PUZZLE;
+    window.nodelay(!is_turn_based);
+
+    let keyboard_input = KeyboardInput::new(&window);
+    let create_controller = || -> Box<dyn Controller + '_> {
+        if uses_keyboard {
+            Box::new(KeyboardController::new(&keyboard_input, FIRST_PLAYER_KEYS))
+        } else {
+            controller::create_bot(&options.controller).expect("controller checked when parsing the options")
+        }
+    };
+    let mut controller = create_controller();
+    let mut second_player_controller = if game_state.tron_opponent == TronOpponent::LOCAL {
+        Some(KeyboardController::new(&keyboard_input, SECOND_PLAYER_KEYS))
+    } else {
+        None
+    };
 
-    let mut game_state = GameState::new();
-    let high_scores = score_manager.get_high_scores(&difficulty, 1);
+    let leaderboard_id = mode.get_leaderboard_id(ruleset);
+    let find_ghost = |game_state: &GameState| if options.ghost {
+        Ghost::find_personal_best(&score_manager, &leaderboard_id, user_name, game_state, ruleset, seed)
+    } else {
+        None
+    };
+    // a resumed game continues on its own seed, so there is no ghost to race
+    let mut ghost = if save_game.is_none() { find_ghost(&game_state) } else { None };
+    if let Some(seed) = ghost.as_ref().map(Ghost::get_seed).or(seed).filter(|_| save_game.is_none()) {
+        game_state.reseed(seed);
+    }
+    let high_scores = score_manager.get_high_scores(&leaderboard_id, 1);
     let mut high_score_display = create_high_score_display(&high_scores);
     let mut stopwatch = stopwatch::Stopwatch::new();
+    // time played before the game was resumed
+    let mut time_offset = Duration::from_millis(save_game.as_ref().map_or(0, |save_game| save_game.time));
+    let mut hints_used = save_game.as_ref().is_some_and(|save_game| save_game.hints);
+    let mut resumed = save_game.is_some();
+    let mut splits = save_game.as_ref().map_or_else(Splits::default, |save_game| save_game.splits.clone());
+    let mut personal_best_splits = Splits::find_personal_best(&score_manager, &leaderboard_id, user_name);
+    // the observations are those of the reinforcement learning environment, which only covers classic games
+    let mut dataset = options.dataset.as_ref()
+        .filter(|_| mode == GameMode::CLASSIC)
+        .map(|_| (Dataset::new(options.observation_kind, false), DatasetRecorder::new(options.observation_kind, 1.0, 0)));
+    let mut replay = match save_game {
+        Some(save_game) => save_game.replay,
+        None => Replay::start(&game_state, ruleset),
+    };
+    // scores are verified against the rulesets as they are defined, not as recorded in the replay
+    let official_rulesets = Ruleset::load_all("rulesets.xml");
+
+    if resumed {
+        window.clear();
+        draw(&window, &game_state, &high_score_display, ruleset, time_offset, None);
+        window.addstr("\n\nRESUMED - press any key to continue");
+        window.refresh();
+        window.nodelay(false);
+        interrupt::read_key(&window);
+        window.nodelay(!is_turn_based);
+    }
 
+    let mut saved_on_abort = false;
+    // the game goes on if it cannot be saved, the reason is shown until saving works again
+    let mut save_error: Option<String> = None;
     while !game_state.game_terminated {
         while !game_state.game_over {
+            let hint = if keyboard_input.shows_hints() { Hint::for_player(&game_state, ruleset) } else { None };
+            hints_used |= hint.is_some();
             window.clear();
-            draw(&window, &snake, &fruit, &game_state, &high_score_display, &difficulty, &stopwatch);
+            draw(&window, &game_state, &high_score_display, ruleset, time_offset + stopwatch.elapsed(), hint.as_ref());
+            // turn-based games are not played against the clock
+            if !is_turn_based {
+                window.addstr(format!("\n{}", splits.get_hud_display(personal_best_splits.as_ref(), time_offset + stopwatch.elapsed())));
+            }
+            if let Some(ghost) = &ghost {
+                window.addstr(format!("\n{}", ghost.get_score_delta_display(&game_state)));
+                ghost.draw(&window);
+            } else if options.ghost {
+                window.addstr("\nGhost:                                  no earlier run to race");
+            }
+            if let Some(message) = &save_error {
+                window.addstr(format!("\nCould not save the game: {}", message));
+            }
             window.refresh();
-            game_state.current_direction = handle_input(&window, game_state.current_direction);
-            handle_stopwatch(&mut stopwatch, &game_state.current_direction);
-            handle_snake_movement(&mut snake, &mut fruit, &difficulty, &mut game_state);
-
-            thread::sleep(Duration::from_millis(difficulty.get_refresh_delay()));
+            let direction = controller.next_direction(&Observation::new(&game_state, ruleset, PLAYER_INDEX));
+            // the game ends where it is, waiting for keys is cut short by the signal
+            if interrupt::get_received_signal().is_some() {
+                break;
+            }
+            if controller.get_disqualification().is_some() {
+                game_state.disqualify(PLAYER_INDEX);
+                break;
+            }
+            // recorded before the move is applied, the heading of the observation must not give away the label
+            if let Some((_, recorder)) = &mut dataset {
+                recorder.record(&game_state, direction);
+            }
+            game_state.player_mut().direction = direction;
+            if let Some(second_player_controller) = &mut second_player_controller {
+                let direction = second_player_controller.next_direction(&Observation::new(&game_state, ruleset, OPPONENT_INDEX));
+                game_state.snakes[OPPONENT_INDEX].direction = direction;
+            }
+            if uses_keyboard {
+                let moving = game_state.player().direction != STOP;
+                let pausing = !moving && stopwatch.is_running();
+                if pausing || (moving && (is_turn_based || game_state.tick_count % AUTOSAVE_INTERVAL == 0)) {
+                    let time = (time_offset + stopwatch.elapsed()).as_millis() as u64;
+                    save_error = SaveGame::write(user_name, &game_state, &replay, time, hints_used, &splits).err();
+                }
+            }
+            replay.record(&game_state);
+            if let Some(ghost) = &mut ghost {
+                ghost.advance(&game_state);
+            }
+            handle_stopwatch(&mut stopwatch, &game_state.player().direction);
+            game_state.handle_snake_movement(ruleset);
+            splits.update(game_state.player().tail_x_pos.len(), time_offset + stopwatch.elapsed());
+
+            if !is_turn_based {
+                thread::sleep(Duration::from_millis(ruleset.get_refresh_delay()));
+            } else if !uses_keyboard {
+                // give spectators a chance to follow the moves of bots
+                thread::sleep(Duration::from_millis(BOT_MOVE_DELAY));
+            }
         }
 
-        score_manager.write_score(game_state.current_score, &difficulty, user_name, stopwatch.elapsed().as_millis());
-        let new_high_scores = score_manager.get_high_scores(&difficulty, 3);
-        print_game_over_screen(game_state.current_score, &new_high_scores, &difficulty, &stopwatch, &window);
+        let aborted_by = interrupt::get_received_signal();
+        if let Some((dataset, recorder)) = &mut dataset {
+            dataset.add(&recorder.finish_game(&game_state));
+        }
+        let time = (time_offset + stopwatch.elapsed()).as_millis() as u64;
+        // an aborted game is recorded as it is and also saved where it stopped to be resumed
+        saved_on_abort = aborted_by.is_some() && uses_keyboard
+            && SaveGame::write(user_name, &game_state, &replay, time, hints_used, &splits).is_ok();
+        replay.finish(&game_state, time, controller.get_disqualification());
+        let replay_file = replay.save();
+        let replay_message = match &replay_file {
+            Ok(file_path) => format!("Replay saved to {}", file_path.display()),
+            Err(message) => format!("Could not save the replay: {}", message),
+        };
+        let replay_message = format!("{}\nChallenge others to this game with the code {}", replay_message, ShareCode::Challenge(Challenge::from_replay(&replay)).encode());
+        // the leaderboards are meant for humans, games played by bots are not recorded
+        if uses_keyboard {
+            if !saved_on_abort {
+                SaveGame::delete(user_name);
+            }
+            let mut score = Score {
+                score: game_state.current_score,
+                user: String::from(user_name),
+                time: Some(time),
+                mutators: game_state.mutators.clone(),
+                hints: hints_used,
+                resumed,
+                aborted: aborted_by.is_some(),
+                tail_length: Some(game_state.player().tail_x_pos.len() as u64),
+                splits: splits.clone(),
+                replay: replay_file.ok().map(|file_path| file_path.to_string_lossy().into_owned()),
+                verified: false,
+            };
+            score.verified = audit::verify_score(&leaderboard_id, &score, &official_rulesets).is_empty();
+            score_manager.write_score(&leaderboard_id, &score);
+        }
+        if aborted_by.is_some() {
+            game_state.game_terminated = true;
+            continue;
+        }
+        let summary = if is_turn_based {
+            replay_message
+        } else {
+            format!("{}\n{}", splits.render_table(personal_best_splits.as_ref()), replay_message)
+        };
+        let new_high_scores = score_manager.get_high_scores(&leaderboard_id, 3);
+        print_game_over_screen(&game_state, &new_high_scores, ruleset, time_offset + stopwatch.elapsed(), controller.get_disqualification(), &summary, &window);
 
         window.nodelay(false);
         loop {
-            match window.getch() {
+            match interrupt::read_key(&window) {
                 Some(Input::Character(RETRY_KEY)) => {
                     high_score_display = create_high_score_display(&new_high_scores);
-                    game_state.current_score = 0;
-                    game_state.game_over = false;
-                    game_state.current_direction = Direction::STOP;
-                    fruit.respawn();
-                    snake.reset();
+                    // a new best run replaces the ghost
+                    ghost = find_ghost(&game_state);
+                    game_state.reseed(ghost.as_ref().map(Ghost::get_seed).or(seed).unwrap_or_else(rand::random));
+                    replay = Replay::start(&game_state, ruleset);
                     stopwatch.reset();
+                    time_offset = Duration::ZERO;
+                    hints_used = false;
+                    resumed = false;
+                    splits = Splits::default();
+                    personal_best_splits = Splits::find_personal_best(&score_manager, &leaderboard_id, user_name);
+                    // bots start over as well, external bots get a fresh process
+                    controller = create_controller();
                     break;
                 }
                 Some(Input::Character(QUIT_KEY)) => {
@@ -129,19 +429,103 @@ fn main() {
                 _ => {}
             }
         }
-        window.nodelay(true);
+        window.nodelay(!is_turn_based);
     }
+
+    let dataset_error = match (&options.dataset, &dataset) {
+        (Some(file_path), Some((dataset, _))) => dataset.write(file_path).err()
+            .map(|error| format!("could not write the dataset to '{}': {}", file_path, error)),
+        _ => None,
+    };
+    if let Some(signal) = interrupt::get_received_signal() {
+        endwin();
+        let elapsed = (time_offset + stopwatch.elapsed()).format_duration();
+        println!("Game aborted with a score of {} after {}", game_state.current_score, elapsed);
+        if saved_on_abort {
+            println!("The game has been saved, enter the same name to resume it");
+        }
+        if let Some(message) = &dataset_error {
+            eprintln!("{}", message);
+        }
+        process::exit(interrupt::get_exit_code(signal));
+    }
+    if let Some(message) = dataset_error {
+        endwin();
+        eprintln!("{}", message);
+        process::exit(1);
+    }
+}
+
+/// Returns the ruleset with the provided id for commands running without a menu, exits if there is
+/// no such ruleset
+fn load_ruleset(id: &str) -> Ruleset {
+    Ruleset::load_all("rulesets.xml").into_iter()
+        .find(|ruleset| ruleset.id == id)
+        .unwrap_or_else(|| {
+            eprintln!("unknown ruleset '{}'", id);
+            process::exit(2);
+        })
+}
+
+/// Starts curses and makes sure the terminal is large enough for the field and the HUD
+fn init_window() -> Window {
+    let window = initscr();
+    noecho();
+    if window.get_max_x() < WINDOW_COLS as i32 || window.get_max_y() < WINDOW_ROWS as i32 {
+        resize_term(WINDOW_ROWS as i32, WINDOW_COLS as i32);
+    }
+
+    window
 }
 
-fn handle_input(window: &Window, curr_dir: Direction) -> Direction {
-    match window.getch() {
-        Some(Input::Character(UP_KEY)) => Direction::UP,
-        Some(Input::Character(DOWN_KEY)) => Direction::DOWN,
-        Some(Input::Character(LEFT_KEY)) => Direction::LEFT,
-        Some(Input::Character(RIGHT_KEY)) => Direction::RIGHT,
-        Some(Input::Character(PAUSE_KEY)) => Direction::STOP,
-        _ => curr_dir
+/// Shows a replay in the viewer until it is closed. Once the terminal is restored the replay is
+/// played to the end and compared against the recorded result, the process exits with 1 if they
+/// differ.
+fn play_replay(replay: &Replay) {
+    let cleanup = Cleanup;
+    let window = init_window();
+    replay_viewer::run(&window, replay);
+    drop(cleanup);
+
+    let game_state = replay.simulate();
+    println!(
+        "replay of {} ({}), seed {}: score {} after {} ticks",
+        replay.mode, replay.ruleset.id, replay.seed, game_state.current_score, game_state.tick_count,
+    );
+    if game_state.current_score != replay.score {
+        println!("does not match the recorded score of {}", replay.score);
+        process::exit(1);
     }
+    println!("matches the recorded result");
+}
+
+/// Shows a demo game played by the autopilot until any key is pressed, a new demo starts whenever
+/// the autopilot loses
+fn run_attract_mode(window: &Window) {
+    let ruleset = Ruleset::built_in().into_iter()
+        .find(|ruleset| ruleset.id == ATTRACT_MODE_RULESET)
+        .expect("attract mode ruleset is built in");
+    let mut game_state = GameState::new(GameMode::CLASSIC, Vec::new());
+    let mut controller = AutopilotController;
+    let stopwatch = Stopwatch::start_new();
+
+    window.nodelay(true);
+    while interrupt::read_key(window).is_none() {
+        window.clear();
+        draw(window, &game_state, "-", &ruleset, stopwatch.elapsed(), None);
+        window.addstr("\n\nDEMO - press any key to start");
+        window.refresh();
+
+        let direction = controller.next_direction(&Observation::new(&game_state, &ruleset, PLAYER_INDEX));
+        game_state.player_mut().direction = direction;
+        game_state.handle_snake_movement(&ruleset);
+        if game_state.game_over {
+            game_state.reseed(rand::random());
+        }
+        thread::sleep(Duration::from_millis(ruleset.get_refresh_delay()));
+    }
+    window.nodelay(false);
+    window.clear();
 }
 
 fn handle_stopwatch(stopwatch: &mut Stopwatch, current_direction: &Direction) {
@@ -152,124 +536,301 @@ fn handle_stopwatch(stopwatch: &mut Stopwatch, current_direction: &Direction) {
     }
 }
 
-fn handle_snake_movement(snake: &mut Snake, fruit: &mut Fruit, difficulty: &Difficulty, game_state: &mut GameState) {
-    snake.move_pos(&game_state.current_direction);
-    // do not use an else if here since moving the snake when is_game_over_on_wall_collision is
-    // false might result in the snake being placed an a fruit, so this should always get checked
-    if snake.x_pos == 0 || snake.x_pos == FIELD_WIDTH - 1 || snake.y_pos == 0 || snake.y_pos == FIELD_HEIGHT - 1 {
-        if difficulty.is_game_over_on_wall_collision() {
-            game_state.game_over = true
-        } else {
-            if snake.x_pos == 0 {
-                snake.x_pos = FIELD_WIDTH - 2;
-            } else if snake.x_pos == FIELD_WIDTH - 1 {
-                snake.x_pos = 1;
+fn draw(window: &Window, game_state: &GameState, high_score_display: &str, ruleset: &Ruleset, elapsed: Duration, hint: Option<&Hint>) {
+    window.addstr(render_frame(game_state, high_score_display, ruleset, elapsed, hint));
+}
+
+/// Renders the field and the HUD as text with one line per row, as drawn to the terminal while
+/// playing
+pub(crate) fn render_frame(game_state: &GameState, high_score_display: &str, ruleset: &Ruleset, elapsed: Duration, hint: Option<&Hint>) -> String {
+    let mut frame = String::new();
+    let player = game_state.player();
+    for row in render_field(game_state, hint) {
+        frame.extend(row);
+        frame.push('\n');
+    }
+    frame.push('\n');
+    frame.push_str(format!("Score:                                  {}", game_state.current_score).as_str());
+    frame.push('\n');
+    frame.push_str(format!("High score (for current ruleset):       {}", high_score_display).as_str());
+    frame.push('\n');
+    frame.push_str(format!("Tail length:                            {}", player.tail_x_pos.len()).as_str());
+    frame.push('\n');
+    frame.push_str(format!("Head pos:                               x: {}\n                                        y: {}", player.x_pos, player.y_pos).as_str());
+    frame.push('\n');
+    frame.push_str(format!("Direction:                              {}", player.direction).as_str());
+    frame.push('\n');
+    if let Some(hint) = hint {
+        frame.push_str(format!("Hint:                                   {}", hint.get_advice()).as_str());
+        frame.push('\n');
+    }
+    frame.push_str(format!("Mode:                                   {}", game_state.mode).as_str());
+    frame.push('\n');
+    if game_state.mode == GameMode::ARENA {
+        frame.push_str(format!("Rivals alive:                           {}/{}", arena::count_rivals_alive(game_state), arena::RIVAL_COUNT).as_str());
+        frame.push('\n');
+    }
+    if game_state.mode == GameMode::TRON {
+        frame.push_str(format!("Opponent:                               {}", game_state.tron_opponent).as_str());
+        frame.push('\n');
+    }
+    if let Some(level) = &game_state.puzzle_level {
+        frame.push_str(format!("Moves:                                  {}/{} (par {})", game_state.tick_count, level.move_budget, level.par).as_str());
+        frame.push('\n');
+        frame.push_str(format!("Fruits left:                            {}", game_state.fruits.len()).as_str());
+        frame.push('\n');
+    }
+    frame.push_str(format!("Ruleset:                                {}", ruleset).as_str());
+    frame.push('\n');
+    frame.push_str(format!("Mutators:                               {}", Mutator::format_list(&game_state.mutators)).as_str());
+    frame.push('\n');
+    frame.push_str(format!("Duration:                               {}", elapsed.format_duration()).as_str());
+
+    frame
+}
+
+/// Returns the symbol drawn on each field, indexed by y and x
+pub(crate) fn render_field(game_state: &GameState, hint: Option<&Hint>) -> Vec<Vec<char>> {
+    let player = game_state.player();
+    let tail_matrices: Vec<Vec<Vec<bool>>> = game_state.snakes.iter()
+        .map(|snake| snake.create_tail_matrix())
+        .collect();
+    let tail_visible = !game_state.mutators.contains(&Mutator::InvisibleTail);
+    let fog_of_war = game_state.mutators.contains(&Mutator::FogOfWar);
+
+    let mut field = Vec::with_capacity(FIELD_HEIGHT as usize);
+    for y in 0..FIELD_HEIGHT {
+        let mut row = Vec::with_capacity(FIELD_WIDTH as usize);
+        for x in 0..FIELD_WIDTH {
+            let hidden_by_fog = fog_of_war
+                && (x.max(player.x_pos) - x.min(player.x_pos) > FOG_OF_WAR_RADIUS || y.max(player.y_pos) - y.min(player.y_pos) > FOG_OF_WAR_RADIUS);
+            let snake_at = |predicate: &dyn Fn(usize) -> bool| {
+                (0..game_state.snakes.len()).find(|i| game_state.snakes[*i].alive && predicate(*i))
+            };
+
+            if game_state.is_wall(x, y) {
+                row.push(WALL_SYMBOL);
+            } else if y == player.y_pos && x == player.x_pos {
+                row.push(HEAD_SYMBOL);
+            } else if hidden_by_fog {
+                row.push(' ');
+            } else if game_state.fruits.iter().any(|fruit| fruit.x_pos == x && fruit.y_pos == y) {
+                row.push(FRUIT_SYMBOL);
+            } else if let Some(i) = snake_at(&|i| game_state.snakes[i].x_pos == x && game_state.snakes[i].y_pos == y) {
+                row.push(if i == PLAYER_INDEX { HEAD_SYMBOL } else { RIVAL_HEAD_SYMBOL });
+            } else if let Some(i) = snake_at(&|i| tail_matrices[i][y as usize][x as usize]) {
+                if i != PLAYER_INDEX {
+                    row.push(RIVAL_TAIL_SYMBOL);
+                } else if tail_visible {
+                    row.push(TAIL_SYMBOL);
+                } else {
+                    row.push(' ');
+                }
+            } else if hint.is_some_and(|hint| hint.path.contains(&(x, y))) {
+                row.push(HINT_SYMBOL);
+            } else {
+                row.push(' ');
             }
-            if snake.y_pos == 0 {
-                snake.y_pos = FIELD_HEIGHT - 2;
-            } else if snake.y_pos == FIELD_HEIGHT - 1 {
-                snake.y_pos = 1;
+        }
+
+        field.push(row);
+    }
+
+    field
+}
+
+fn select_mode(window: &Window) -> GameMode {
+    loop {
+        print_mode_selection(window);
+        if let Some(Input::Character(input_char)) = interrupt::read_key(window) {
+            let digit_conversion = input_char.to_digit(10);
+            if let Some(mode) = digit_conversion.and_then(|digit| GameMode::all().get(digit as usize).copied()) {
+                window.clear();
+                return mode;
             }
+
+            window.clear();
+            window.addstr(format!("Could not get mode for {}", input_char));
+            window.addch('\n');
+            window.refresh();
         }
     }
+}
+
+fn print_mode_selection(window: &Window) {
+    window.addstr("Select mode:");
+    for (i, mode) in GameMode::all().iter().enumerate() {
+        window.addch('\n');
+        window.addstr(format!("{} - {}: {}", i, mode, mode.get_description()).as_str());
+    }
+    window.refresh();
+}
 
-    for i in 0..snake.tail_x_pos.len() {
-        if snake.tail_x_pos[i] == snake.x_pos && snake.tail_y_pos[i] == snake.y_pos {
-            game_state.game_over = true
+fn select_tron_opponent(window: &Window) -> TronOpponent {
+    loop {
+        print_tron_opponent_selection(window);
+        if let Some(Input::Character(input_char)) = interrupt::read_key(window) {
+            let digit_conversion = input_char.to_digit(10);
+            if let Some(opponent) = digit_conversion.and_then(|digit| TronOpponent::all().get(digit as usize).copied()) {
+                window.clear();
+                return opponent;
+            }
+
+            window.clear();
+            window.addstr(format!("Could not get opponent for {}", input_char));
+            window.addch('\n');
+            window.refresh();
         }
     }
+}
 
-    if snake.x_pos == fruit.x_pos && snake.y_pos == fruit.y_pos {
-        game_state.current_score += 5 * difficulty.get_score_multiplier() as u64;
-        snake.append_tail();
-        fruit.respawn();
+fn print_tron_opponent_selection(window: &Window) {
+    window.addstr("Select opponent:");
+    for (i, opponent) in TronOpponent::all().iter().enumerate() {
+        window.addch('\n');
+        window.addstr(format!("{} - {}: {}", i, opponent, opponent.get_description()).as_str());
     }
+    window.refresh();
 }
 
-fn draw(window: &Window, snake: &Snake, fruit: &Fruit, game_state: &GameState, high_score_display: &String, difficulty: &Difficulty, stopwatch: &Stopwatch) {
-    let max_y_index = FIELD_HEIGHT - 1;
-    let max_x_index = FIELD_WIDTH - 1;
-    let tail_matrix = snake.create_tail_matrix();
+fn select_puzzle_level(window: &Window) -> &'static PuzzleLevel {
+    let levels = PuzzleLevel::built_in();
 
-    for y in 0..FIELD_HEIGHT {
-        for x in 0..FIELD_WIDTH {
-            if y == 0 || x == 0 || y == max_y_index || x == max_x_index {
-                window.addch(WALL_SYMBOL);
-            } else if y == snake.y_pos && x == snake.x_pos {
-                window.addch(HEAD_SYMBOL);
-            } else if y == fruit.y_pos && x == fruit.x_pos {
-                window.addch(FRUIT_SYMBOL);
-            } else if tail_matrix[y as usize][x as usize] {
-                window.addch(TAIL_SYMBOL);
-            } else {
-                window.addch(' ');
+    loop {
+        print_puzzle_level_selection(window, levels);
+        if let Some(Input::Character(input_char)) = interrupt::read_key(window) {
+            let digit_conversion = input_char.to_digit(10);
+            if let Some(level) = digit_conversion.and_then(|digit| levels.get(digit as usize)) {
+                window.clear();
+                return level;
+            }
+
+            window.clear();
+            window.addstr(format!("Could not get level for {}", input_char));
+            window.addch('\n');
+            window.refresh();
+        }
+    }
+}
+
+fn print_puzzle_level_selection(window: &Window, levels: &[PuzzleLevel]) {
+    window.addstr("Select level:");
+    for (i, level) in levels.iter().enumerate() {
+        window.addch('\n');
+        window.addstr(format!("{} - {}: {} ({} fruits, par {}, budget {})", i, level.id, level.description, level.fruits.len(), level.par, level.move_budget).as_str());
+    }
+    window.refresh();
+}
+
+fn select_ruleset<'a>(window: &Window, rulesets: &'a [Ruleset]) -> &'a Ruleset {
+    loop {
+        print_ruleset_selection(window, rulesets);
+        if let Some(Input::Character(input_char)) = interrupt::read_key(window) {
+            // rulesets are numbered 0-9 followed by a-z, which is what a radix of 36 parses
+            let digit_conversion = input_char.to_digit(36);
+            if let Some(ruleset) = digit_conversion.and_then(|digit| rulesets.get(digit as usize)) {
+                return ruleset;
             }
+
+            window.clear();
+            window.addstr(format!("Could not get ruleset for {}", input_char));
+            window.addch('\n');
+            window.refresh();
         }
+    }
+}
 
+fn print_ruleset_selection(window: &Window, rulesets: &[Ruleset]) {
+    window.addstr("Select ruleset:");
+    // only the rulesets that can be selected with a single key are listed
+    for (i, ruleset) in rulesets.iter().enumerate().take(36) {
         window.addch('\n');
+        let key = std::char::from_digit(i as u32, 36).expect("index out of range");
+        window.addstr(format!("{} - {}: {}", key, ruleset, ruleset.get_description()).as_str());
     }
-    window.addch('\n');
-    window.addstr(format!("Score:                                  {}", game_state.current_score).as_str());
-    window.addch('\n');
-    window.addstr(format!("High score (for current difficulty):    {}", high_score_display).as_str());
-    window.addch('\n');
-    window.addstr(format!("Tail length:                            {}", snake.tail_x_pos.len()).as_str());
-    window.addch('\n');
-    window.addstr(format!("Head pos:                               x: {}\n                                        y: {}", snake.x_pos, snake.y_pos).as_str());
-    window.addch('\n');
-    window.addstr(format!("Direction:                              {}", game_state.current_direction).as_str());
-    window.addch('\n');
-    window.addstr(format!("Difficulty:                             {}", difficulty).as_str());
-    window.addch('\n');
-    window.addstr(format!("Duration:                               {}", stopwatch.elapsed().format_duration()).as_str());
+    window.refresh();
 }
 
-fn select_difficulty(window: &Window) -> Difficulty {
+fn select_mutators(window: &Window, offered_mutators: &[Mutator]) -> Vec<Mutator> {
+    let mut selected = vec![false; offered_mutators.len()];
+
     loop {
-        print_difficulty_selection(window);
-        match window.getch() {
+        window.clear();
+        print_mutator_selection(window, offered_mutators, &selected);
+        match interrupt::read_key(window) {
+            Some(Input::Character(CONFIRM_KEY)) => {
+                window.clear();
+                return offered_mutators.iter()
+                    .zip(selected.iter())
+                    .filter(|(_, is_selected)| **is_selected)
+                    .map(|(mutator, _)| *mutator)
+                    .collect();
+            }
             Some(Input::Character(input_char)) => {
-                let digit_conversion = input_char.to_digit(10);
-                if let Some(digit) = digit_conversion {
-                    let difficulty_conversion: Result<Difficulty, _> = digit.try_into();
-                    if let Ok(difficulty) = difficulty_conversion {
-                        return difficulty;
+                if let Some(digit) = input_char.to_digit(10) {
+                    if let Some(is_selected) = selected.get_mut(digit as usize) {
+                        *is_selected = !*is_selected;
                     }
                 }
-
-                window.clear();
-                window.addstr(format!("Could net get difficulty for {}", input_char));
-                window.addch('\n');
-                window.refresh();
             }
             _ => {}
         }
     }
 }
 
-fn print_difficulty_selection(window: &Window) {
-    window.addstr("Select difficulty:");
-    window.addch('\n');
-    window.addstr(format!("{} - {}: {}", Difficulty::EASY as u8, Difficulty::EASY, Difficulty::EASY.get_description()).as_str());
-    window.addch('\n');
-    window.addstr(format!("{} - {}: {}", Difficulty::ARCADE as u8, Difficulty::ARCADE, Difficulty::ARCADE.get_description()).as_str());
-    window.addch('\n');
-    window.addstr(format!("{} - {}: {}", Difficulty::NORMAL as u8, Difficulty::NORMAL, Difficulty::NORMAL.get_description()).as_str());
-    window.addch('\n');
-    window.addstr(format!("{} - {}: {}", Difficulty::HARD as u8, Difficulty::HARD, Difficulty::HARD.get_description()).as_str());
+fn print_mutator_selection(window: &Window, mutators: &[Mutator], selected: &[bool]) {
+    window.addstr("Toggle mutators, press enter to start:");
+    for (i, mutator) in mutators.iter().enumerate() {
+        window.addch('\n');
+        let checkbox = if selected[i] { "[x]" } else { "[ ]" };
+        window.addstr(format!("{} {} - {} (+{}%): {}", checkbox, i, mutator, mutator.get_score_bonus(), mutator.get_description()).as_str());
+    }
     window.refresh();
 }
 
-fn create_high_score_display(high_score_vec: &Vec<(u64, String, Option<u64>)>) -> String {
+fn create_high_score_display(high_score_vec: &[Score]) -> String {
     if high_score_vec.is_empty() {
         String::from("0")
     } else {
-        let high_score_tuple = &high_score_vec[0];
-        let time_string = high_score_tuple.2.format_duration();
-        String::from(high_score_tuple.0.to_string()).add(" (").add(high_score_tuple.1.as_str()).add(")").add(time_string.as_str())
+        let high_score = &high_score_vec[0];
+        let time_string = high_score.time.format_duration();
+        let mut display = high_score.score.to_string().add(" (").add(high_score.user.as_str()).add(")").add(time_string.as_str());
+        if high_score.hints {
+            display = display.add(" (hints)");
+        }
+        if high_score.resumed {
+            display = display.add(" (resumed)");
+        }
+        display
     }
 }
 
+/// Offers to resume the game the user saved last, returns it if the user wants to
+fn offer_save_game(window: &Window, user_name: &str) -> Option<SaveGame> {
+    let save_game = match SaveGame::read(user_name) {
+        Ok(Some(save_game)) => save_game,
+        Ok(None) => return None,
+        Err(message) => {
+            window.addstr(format!("Could not resume the saved game: {}\n\nPress any key to start a new game.", message));
+            window.refresh();
+            interrupt::read_key(window);
+            window.clear();
+            return None;
+        }
+    };
+
+    window.addstr(format!(
+        "You have a saved {} game ({}) with a score of {} after {}.\n\nPress {} to resume it or any other key to start a new game.",
+        save_game.game_state.mode, save_game.replay.ruleset.id, save_game.game_state.current_score,
+        Duration::from_millis(save_game.time).format_duration(), RESUME_KEY,
+    ));
+    window.refresh();
+    let resume = interrupt::read_key(window) == Some(Input::Character(RESUME_KEY));
+    window.clear();
+
+    if resume { Some(save_game) } else { None }
+}
+
 fn print_start_screen(playtime_display: String) {
     println!(r#"
  ______   ___   __    ________   ___   ___   ______
@@ -287,14 +848,15 @@ W:  UP
 S:  DOWN
 A:  LEFT
 D:  RIGHT
-P:  PAUSE (click any other direction to unpause)
+P:  PAUSE (saves the game, click any other direction to unpause)
+H:  HINTS (toggle, scores of games with hints are flagged)
 ___________________
 Enter player name:
 -------------------
     "#, playtime_display);
 }
 
-fn print_game_over_screen(current_score: u64, high_scores: &Vec<(u64, String, Option<u64>)>, difficulty: &Difficulty, stopwatch: &Stopwatch, window: &Window) {
+fn print_game_over_screen(game_state: &GameState, high_scores: &[Score], ruleset: &Ruleset, elapsed: Duration, disqualification: Option<&str>, summary: &str, window: &Window) {
     window.clear();
     window.refresh();
     let game_over_text = r#"
@@ -308,17 +870,61 @@ fn print_game_over_screen(current_score: u64, high_scores: &Vec<(u64, String, Op
 
     "#;
 
-    let mut output = String::from(game_over_text).add("\nYour score:\n")
-        .add(current_score.to_string().as_str()).add("\n\n")
+    let mut output = String::from(game_over_text);
+    if let Some(reason) = disqualification {
+        output.push_str(format!("The bot has been disqualified: {}\n\n", reason).as_str());
+    }
+    if game_state.mode == GameMode::CLASSIC && game_state.won {
+        output.push_str("Perfect game, the snake fills the whole board!\n\n");
+    }
+    if game_state.mode == GameMode::ARENA {
+        let arena_result = if game_state.won { "You are the last snake alive!\n\n" } else { "You have been eliminated.\n\n" };
+        output.push_str(arena_result);
+    }
+    if game_state.mode == GameMode::TRON && game_state.tron_opponent != TronOpponent::NONE {
+        let versus_result = if game_state.won {
+            "You win!\n\n"
+        } else if game_state.snakes.iter().all(|snake| !snake.alive) {
+            "Draw, both light cycles crashed.\n\n"
+        } else {
+            "Your opponent wins.\n\n"
+        };
+        output.push_str(versus_result);
+    }
+    if let Some(level) = &game_state.puzzle_level {
+        let puzzle_result = if game_state.won {
+            format!("Level solved in {} moves (par {})!\n\n", game_state.tick_count, level.par)
+        } else if game_state.player().alive {
+            String::from("Out of moves.\n\n")
+        } else {
+            String::from("You crashed.\n\n")
+        };
+        output.push_str(puzzle_result.as_str());
+    }
+
+    output = output.add("\nYour score:\n")
+        .add(game_state.current_score.to_string().as_str()).add("\n\n")
         .add("Your time:\n")
-        .add(stopwatch.elapsed().format_duration().as_str()).add("\n\n\n")
-        .add("High scores (").add(difficulty.to_string().as_str()).add(")\n");
+        .add(elapsed.format_duration().as_str()).add("\n\n\n")
+        .add("High scores (").add(game_state.mode.get_leaderboard_id(ruleset).as_str()).add(")\n");
 
-    for score_tuple in high_scores {
-        let line = String::from(score_tuple.1.as_str()).add(":\t\t\t").add(score_tuple.0.to_string().as_str()).add(score_tuple.2.format_duration().as_str()).add("\n");
+    for score in high_scores {
+        let mut line = String::from(score.user.as_str()).add(":\t\t\t").add(score.score.to_string().as_str()).add(score.time.format_duration().as_str());
+        if !score.mutators.is_empty() {
+            line = line.add(" [").add(Mutator::format_list(&score.mutators).as_str()).add("]");
+        }
+        if score.hints {
+            line = line.add(" (hints)");
+        }
+        if score.resumed {
+            line = line.add(" (resumed)");
+        }
+        line.push('\n');
         output.push_str(line.as_str());
     }
 
+    output.push('\n');
+    output.push_str(summary);
     output.push_str("\n\nPress r to retry or q to quit.");
     window.addstr(output.as_str());
     window.refresh();
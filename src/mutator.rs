@@ -0,0 +1,111 @@
+use std::fmt;
+
+use crate::direction::Direction;
+use crate::game_mode::GameMode;
+
+/// Optional modifier that can be toggled before a game, each active mutator changes the rules and
+/// increases the score multiplier by its bonus.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mutator {
+    InvertedControls,
+    InvisibleTail,
+    FogOfWar,
+    DriftingFruit,
+    DoubleGrowth,
+}
+
+/// Radius around the head that stays visible when FogOfWar is active
+pub const FOG_OF_WAR_RADIUS: u16 = 6;
+/// Number of ticks between each step of the fruit when DriftingFruit is active
+pub const FRUIT_DRIFT_INTERVAL: u64 = 4;
+
+impl fmt::Display for Mutator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_id())
+    }
+}
+
+impl Mutator {
+    /// Returns all mutators in menu order
+    pub fn all() -> [Mutator; 5] {
+        [
+            Mutator::InvertedControls,
+            Mutator::InvisibleTail,
+            Mutator::FogOfWar,
+            Mutator::DriftingFruit,
+            Mutator::DoubleGrowth,
+        ]
+    }
+
+    /// Returns the mutators offered in the mode in menu order. Tron has no fruit to drift or to grow
+    /// by and puzzle levels are played as designed.
+    pub fn all_for_mode(mode: GameMode) -> Vec<Mutator> {
+        match mode {
+            GameMode::CLASSIC | GameMode::ARENA => Self::all().to_vec(),
+            GameMode::TRON => vec![Mutator::InvertedControls, Mutator::InvisibleTail, Mutator::FogOfWar],
+            GameMode::PUZZLE => Vec::new(),
+        }
+    }
+
+    /// Returns an error naming the first mutator that is not offered in the mode
+    pub fn check_mode(mutators: &[Mutator], mode: GameMode) -> Result<(), String> {
+        match mutators.iter().find(|mutator| !Self::all_for_mode(mode).contains(mutator)) {
+            Some(mutator) => Err(format!("mutator '{}' is not offered in {} mode", mutator, mode)),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the stable id used when recording the mutator with a score
+    pub fn get_id(&self) -> &'static str {
+        match self {
+            Mutator::InvertedControls => "INVERTED_CONTROLS",
+            Mutator::InvisibleTail => "INVISIBLE_TAIL",
+            Mutator::FogOfWar => "FOG_OF_WAR",
+            Mutator::DriftingFruit => "DRIFTING_FRUIT",
+            Mutator::DoubleGrowth => "DOUBLE_GROWTH",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Mutator> {
+        Self::all().iter().copied().find(|mutator| mutator.get_id() == id)
+    }
+
+    pub(crate) fn get_description(&self) -> &str {
+        match self {
+            Mutator::InvertedControls => "Every direction key steers the opposite way",
+            Mutator::InvisibleTail => "Only the head of the snake is drawn",
+            Mutator::FogOfWar => "Only the area around the head is visible",
+            Mutator::DriftingFruit => "The fruit wanders around the field",
+            Mutator::DoubleGrowth => "The tail grows twice as much per fruit",
+        }
+    }
+
+    /// Returns the bonus in percent this mutator adds to the score multiplier, bonuses are multiples
+    /// of 20 so that the points for a fruit (5 * multiplier) stay integral
+    pub(crate) fn get_score_bonus(&self) -> u64 {
+        match self {
+            Mutator::InvertedControls => 40,
+            Mutator::InvisibleTail => 60,
+            Mutator::FogOfWar => 60,
+            Mutator::DriftingFruit => 20,
+            Mutator::DoubleGrowth => 40,
+        }
+    }
+
+    /// Applies the InvertedControls mutator to a newly chosen direction if it is active
+    pub fn apply_to_direction(mutators: &[Mutator], direction: Direction) -> Direction {
+        if mutators.contains(&Mutator::InvertedControls) {
+            direction.opposite()
+        } else {
+            direction
+        }
+    }
+
+    pub fn format_list(mutators: &[Mutator]) -> String {
+        if mutators.is_empty() {
+            return String::from("none");
+        }
+
+        mutators.iter().map(|mutator| mutator.get_id()).collect::<Vec<&str>>().join(", ")
+    }
+}
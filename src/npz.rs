@@ -0,0 +1,120 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/// Writes arrays to a `.npz` file as read by `numpy.load`: a zip archive without compression holding
+/// one `.npy` file per array
+pub struct NpzWriter {
+    /// name, contents of the `.npy` file and CRC-32 of each array
+    entries: Vec<(String, Vec<u8>, u32)>,
+}
+
+impl NpzWriter {
+    pub fn new() -> Self {
+        NpzWriter { entries: Vec::new() }
+    }
+
+    /// Adds an array in row-major order. The dtype is the numpy type string, e.g. `<f4` for little
+    /// endian f32 or `|u1` for u8, the data holds the values in that encoding.
+    pub fn add_array(&mut self, name: &str, dtype: &str, shape: &[usize], data: &[u8]) {
+        let shape = match shape {
+            [length] => format!("({},)", length),
+            _ => format!("({})", shape.iter().map(|dimension| dimension.to_string()).collect::<Vec<_>>().join(", ")),
+        };
+        let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", dtype, shape);
+        // magic, version and header length take 10 bytes, the data starts aligned to 64 bytes
+        while !(10 + header.len() + 1).is_multiple_of(64) {
+            header.push(' ');
+        }
+        header.push('\n');
+
+        let mut npy = Vec::with_capacity(10 + header.len() + data.len());
+        npy.extend_from_slice(b"\x93NUMPY\x01\x00");
+        npy.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        npy.extend_from_slice(header.as_bytes());
+        npy.extend_from_slice(data);
+
+        let crc = crc32(&npy);
+        self.entries.push((format!("{}.npy", name), npy, crc));
+    }
+
+    pub fn write(&self, file_path: &str) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(file_path)?);
+        let mut central_directory = Vec::new();
+        let mut offset: usize = 0;
+
+        for (name, npy, crc) in self.entries.iter() {
+            // without zip64 extensions sizes and offsets are limited to 32 bits
+            if npy.len() > u32::MAX as usize || offset > u32::MAX as usize {
+                return Err(io::Error::other("arrays larger than 4 GiB are not supported"));
+            }
+
+            let mut local_header = Vec::new();
+            local_header.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+            push_entry_fields(&mut local_header, name, npy.len() as u32, *crc);
+            local_header.extend_from_slice(name.as_bytes());
+
+            central_directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+            // version made by
+            central_directory.extend_from_slice(&20u16.to_le_bytes());
+            push_entry_fields(&mut central_directory, name, npy.len() as u32, *crc);
+            // comment length, disk number, internal and external attributes
+            central_directory.extend_from_slice(&[0; 10]);
+            central_directory.extend_from_slice(&(offset as u32).to_le_bytes());
+            central_directory.extend_from_slice(name.as_bytes());
+
+            file.write_all(&local_header)?;
+            file.write_all(npy)?;
+            offset += local_header.len() + npy.len();
+        }
+
+        let mut end_of_directory = Vec::new();
+        end_of_directory.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        // number of this disk and of the disk holding the central directory
+        end_of_directory.extend_from_slice(&[0; 4]);
+        end_of_directory.extend_from_slice(&(self.entries.len() as u16).to_le_bytes());
+        end_of_directory.extend_from_slice(&(self.entries.len() as u16).to_le_bytes());
+        end_of_directory.extend_from_slice(&(central_directory.len() as u32).to_le_bytes());
+        end_of_directory.extend_from_slice(&(offset as u32).to_le_bytes());
+        // comment length
+        end_of_directory.extend_from_slice(&[0; 2]);
+
+        file.write_all(&central_directory)?;
+        file.write_all(&end_of_directory)?;
+        file.flush()
+    }
+}
+
+/// Appends the fields shared by the local header and the central directory, from the version needed
+/// to extract up to the length of the extra field
+fn push_entry_fields(buffer: &mut Vec<u8>, name: &str, size: u32, crc: u32) {
+    // version needed, flags and compression method (stored)
+    buffer.extend_from_slice(&[20, 0, 0, 0, 0, 0]);
+    // modification time and date, 1980-01-01 00:00
+    buffer.extend_from_slice(&[0, 0, 0x21, 0]);
+    buffer.extend_from_slice(&crc.to_le_bytes());
+    // compressed and uncompressed size
+    buffer.extend_from_slice(&size.to_le_bytes());
+    buffer.extend_from_slice(&size.to_le_bytes());
+    buffer.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    // extra field length
+    buffer.extend_from_slice(&[0; 2]);
+}
+
+/// The CRC-32 checksum used by zip archives
+fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let mut value = i as u32;
+        for _ in 0..8 {
+            value = if value & 1 == 1 { (value >> 1) ^ 0xEDB8_8320 } else { value >> 1 };
+        }
+        *entry = value;
+    }
+
+    let mut crc = !0u32;
+    for byte in data {
+        crc = table[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+
+    !crc
+}
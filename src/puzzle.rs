@@ -0,0 +1,203 @@
+use crate::ruleset::Ruleset;
+
+/// Points awarded for each move of the budget left over when a level is solved
+pub const UNUSED_MOVE_BONUS: u64 = 10;
+
+/// A level of the turn-based puzzle mode. The snake spawns in the middle of the field and only moves
+/// when a direction key is pressed, all fruits have to be eaten within the move budget.
+#[derive(Debug, Clone)]
+pub struct PuzzleLevel {
+    pub(crate) id: &'static str,
+    pub(crate) description: &'static str,
+    pub(crate) fruits: &'static [(u16, u16)],
+    pub(crate) growth_per_fruit: u16,
+    pub(crate) move_budget: u64,
+    /// least number of moves needed to solve the level, checked by the solver in the tests
+    pub(crate) par: u64,
+}
+
+const LEVELS: [PuzzleLevel; 6] = [
+    PuzzleLevel {
+        id: "L1",
+        description: "Warm up",
+        fruits: &[(25, 20), (30, 20), (30, 25)],
+        growth_per_fruit: 1,
+        move_budget: 20,
+        par: 15,
+    },
+    PuzzleLevel {
+        id: "L2",
+        description: "Double back",
+        fruits: &[(27, 25), (29, 25), (23, 25)],
+        growth_per_fruit: 4,
+        move_budget: 11,
+        par: 8,
+    },
+    PuzzleLevel {
+        id: "L3",
+        description: "Zig zag",
+        fruits: &[(27, 25), (27, 28), (24, 28), (24, 31), (27, 31), (27, 34)],
+        growth_per_fruit: 3,
+        move_budget: 23,
+        par: 17,
+    },
+    PuzzleLevel {
+        id: "L4",
+        description: "Hairpin",
+        fruits: &[(25, 22), (25, 28), (25, 21), (25, 29)],
+        growth_per_fruit: 4,
+        move_budget: 19,
+        par: 14,
+    },
+    PuzzleLevel {
+        id: "L5",
+        description: "Spiral",
+        fruits: &[(26, 25), (26, 23), (23, 23), (23, 27), (28, 27), (28, 21), (21, 21)],
+        growth_per_fruit: 3,
+        move_budget: 35,
+        par: 26,
+    },
+    PuzzleLevel {
+        id: "L6",
+        description: "Ring",
+        fruits: &[(24, 24), (25, 24), (26, 24), (24, 25), (26, 25), (24, 26), (25, 26), (26, 26)],
+        growth_per_fruit: 4,
+        move_budget: 11,
+        par: 8,
+    },
+];
+
+impl PuzzleLevel {
+    /// Returns the built-in levels in menu order
+    pub fn built_in() -> &'static [PuzzleLevel] {
+        &LEVELS
+    }
+
+    /// Returns the ruleset puzzle levels are played with: walls are dangerous and the tail grows by
+    /// the amount specified by the level. The id of the level serves as id of the ruleset so that each
+    /// level gets its own leaderboard.
+    pub fn to_ruleset(&self) -> Ruleset {
+        Ruleset {
+            id: String::from(self.id),
+            name: String::from(self.id),
+            description: String::from(self.description),
+            wall_wrap: false,
+            refresh_delay: 0,
+            growth_per_fruit: self.growth_per_fruit,
+            score_multiplier: 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Reverse;
+    use std::collections::{BinaryHeap, HashMap};
+
+    use super::*;
+    use crate::direction::Direction;
+    use crate::game_state::GameState;
+
+    /// The position of the head, the tail and the fruits left
+    type StateKey = (u16, u16, Vec<u16>, Vec<u16>, Vec<(u16, u16)>);
+
+    #[test]
+    fn built_in_levels_are_solved_in_par() {
+        for level in PuzzleLevel::built_in() {
+            match solve(level) {
+                Some(moves) => assert_eq!(moves, level.par, "par of puzzle level {} is not the least number of moves", level.id),
+                None => panic!("puzzle level {} cannot be solved within {} moves", level.id, level.move_budget),
+            }
+        }
+    }
+
+    /// Returns the least number of moves needed to eat all fruits of the level or None if that is not
+    /// possible within the move budget. Each move is simulated with the regular game logic, the search
+    /// uses A* with the distance to the closest fruit plus the minimum spanning tree of the remaining
+    /// fruits as heuristic, which never overestimates since all of them still have to be visited.
+    fn solve(level: &PuzzleLevel) -> Option<u64> {
+        let ruleset = level.to_ruleset();
+        let mut states = vec![GameState::new_puzzle(level.clone())];
+        let mut open = BinaryHeap::new();
+        // least number of moves each state has been reached with, a state is expanded again if it is
+        // reached with fewer moves later on
+        let mut best_moves = HashMap::new();
+        best_moves.insert(state_key(&states[0]), 0);
+        open.push(Reverse((estimate_remaining_moves(&states[0]), 0, 0)));
+
+        while let Some(Reverse((_, moves, state_index))) = open.pop() {
+            if states[state_index].won {
+                return Some(moves);
+            }
+            if moves >= level.move_budget || best_moves[&state_key(&states[state_index])] < moves {
+                continue;
+            }
+
+            for direction in [Direction::UP, Direction::DOWN, Direction::LEFT, Direction::RIGHT].iter() {
+                let mut next_state = states[state_index].clone();
+                next_state.player_mut().direction = *direction;
+                next_state.handle_snake_movement(&ruleset);
+                if !next_state.player().alive {
+                    continue;
+                }
+
+                let key = state_key(&next_state);
+                if best_moves.get(&key).is_none_or(|best| moves + 1 < *best) {
+                    best_moves.insert(key, moves + 1);
+                    let estimate = moves + 1 + estimate_remaining_moves(&next_state);
+                    states.push(next_state);
+                    open.push(Reverse((estimate, moves + 1, states.len() - 1)));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns the position of the snake and the fruits left, which is all that differs between
+    /// states of the same level
+    fn state_key(game_state: &GameState) -> StateKey {
+        let player = game_state.player();
+        (
+            player.x_pos,
+            player.y_pos,
+            player.tail_x_pos.clone(),
+            player.tail_y_pos.clone(),
+            game_state.fruits.iter().map(|fruit| (fruit.x_pos, fruit.y_pos)).collect(),
+        )
+    }
+
+    fn estimate_remaining_moves(game_state: &GameState) -> u64 {
+        let player = game_state.player();
+        let head = (player.x_pos, player.y_pos);
+        let fruits: Vec<(u16, u16)> = game_state.fruits.iter().map(|fruit| (fruit.x_pos, fruit.y_pos)).collect();
+        let closest_fruit = fruits.iter().map(|fruit| distance(head, *fruit)).min().unwrap_or(0);
+
+        // Prim's algorithm, the number of fruits is small enough to not bother with a priority queue
+        let mut in_tree = vec![false; fruits.len()];
+        let mut min_edge = vec![u64::MAX; fruits.len()];
+        let mut tree_length = 0;
+        if !fruits.is_empty() {
+            min_edge[0] = 0;
+        }
+        for _ in 0..fruits.len() {
+            let next = (0..fruits.len())
+                .filter(|i| !in_tree[*i])
+                .min_by_key(|i| min_edge[*i])
+                .expect("fruit left to add to tree");
+            in_tree[next] = true;
+            tree_length += min_edge[next];
+            for i in 0..fruits.len() {
+                if !in_tree[i] {
+                    min_edge[i] = min_edge[i].min(distance(fruits[next], fruits[i]));
+                }
+            }
+        }
+
+        closest_fruit + tree_length
+    }
+
+    fn distance(a: (u16, u16), b: (u16, u16)) -> u64 {
+        (a.0.max(b.0) - a.0.min(b.0) + a.1.max(b.1) - a.1.min(b.1)) as u64
+    }
+}
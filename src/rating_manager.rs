@@ -0,0 +1,139 @@
+use std::fs::File;
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use quick_xml::{Reader, Writer};
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
+use quick_xml::events::attributes::Attribute;
+
+/// Rating every controller starts out with
+pub const INITIAL_ELO: f64 = 1500.0;
+
+/// The Elo rating of a controller
+#[derive(Clone)]
+pub struct Rating {
+    pub(crate) controller: String,
+    pub(crate) elo: f64,
+    pub(crate) matches: u64,
+}
+
+impl Rating {
+    pub fn new(controller: &str) -> Self {
+        Rating {
+            controller: String::from(controller),
+            elo: INITIAL_ELO,
+            matches: 0,
+        }
+    }
+}
+
+/// Ratings of matches with different formats or rulesets are not comparable, so the ratings file
+/// groups them by pool elements named after the format and ruleset (see tournament::get_pool_id)
+pub struct RatingManager<'a> {
+    file_path: &'a str
+}
+
+impl<'a> RatingManager<'a> {
+    pub fn from_file(file_path: &'a str) -> RatingManager<'a> {
+        RatingManager { file_path }
+    }
+
+    /// Returns the ratings of the pool, controllers that have not been rated yet are missing
+    pub fn get_ratings(&self, pool_id: &str) -> Vec<Rating> {
+        self.read_pools().into_iter()
+            .find(|(id, _)| id == pool_id)
+            .map(|(_, ratings)| ratings)
+            .unwrap_or_default()
+    }
+
+    /// Replaces the ratings of the pool, the other pools are written back unchanged
+    pub fn write_ratings(&self, pool_id: &str, ratings: &[Rating]) {
+        let mut pools = self.read_pools();
+        match pools.iter_mut().find(|(id, _)| id == pool_id) {
+            Some((_, pool_ratings)) => *pool_ratings = ratings.to_vec(),
+            None => pools.push((String::from(pool_id), ratings.to_vec())),
+        }
+
+        let mut writer = Writer::new_with_indent(Cursor::new(Vec::new()), b' ', 2);
+        writer.write_event(Event::Decl(BytesDecl::new(b"1.0", Some(b"UTF-8"), None))).expect("failed to write elem");
+        writer.write_event(Event::Start(BytesStart::borrowed_name(b"ratings"))).expect("failed to write elem");
+        for (id, pool_ratings) in pools.iter() {
+            let mut pool_elem = BytesStart::owned(b"pool".to_vec(), "pool".len());
+            pool_elem.push_attribute(("name", id.as_str()));
+
+            writer.write_event(Event::Start(pool_elem)).expect("failed to write elem");
+            for rating in pool_ratings {
+                writer.write_event(Event::Empty(Self::create_rating_elem(rating))).expect("failed to write elem");
+            }
+            writer.write_event(Event::End(BytesEnd::borrowed(b"pool"))).expect("failed to write elem");
+        }
+        writer.write_event(Event::End(BytesEnd::borrowed(b"ratings"))).expect("failed to write elem");
+
+        let mut file = File::create(self.file_path).expect("could not open ratings file");
+        let bytes = writer.into_inner().into_inner();
+        file.write_all(bytes.as_slice()).expect("failed writing ratings to file");
+    }
+
+    /// Returns the ratings of all pools in file order
+    fn read_pools(&self) -> Vec<(String, Vec<Rating>)> {
+        let path = Path::new(self.file_path);
+        if !path.exists() {
+            return Vec::new();
+        }
+
+        let mut xml_reader = Reader::from_file(path).expect("failed to initialize xml reader");
+        let mut buf = Vec::new();
+        let mut pools: Vec<(String, Vec<Rating>)> = Vec::new();
+
+        loop {
+            match xml_reader.read_event(&mut buf) {
+                Ok(Event::Start(ref elem)) if elem.name() == b"pool" => {
+                    let name = elem.attributes()
+                        .map(|attr| attr.expect("could not read attribute"))
+                        .find(|attr| attr.key == b"name")
+                        .expect("pool is missing the name attribute")
+                        .unescape_and_decode_value(&xml_reader)
+                        .expect("could not decode attribute");
+                    pools.push((name, Vec::new()));
+                }
+                Ok(Event::Empty(ref elem)) if elem.name() == b"rating" => {
+                    let mut controller: Option<String> = None;
+                    let mut elo = INITIAL_ELO;
+                    let mut matches = 0;
+
+                    for attr in elem.attributes() {
+                        let attribute: Attribute = attr.expect("could not read attribute");
+                        let value = attribute.unescape_and_decode_value(&xml_reader).expect("could not decode attribute");
+
+                        match attribute.key {
+                            b"controller" => controller = Some(value),
+                            b"elo" => elo = value.parse().expect("could not parse value of attribute elo as f64"),
+                            b"matches" => matches = value.parse().expect("could not parse value of attribute matches as u64"),
+                            _ => {}
+                        }
+                    }
+
+                    let controller = controller.expect("rating is missing the controller attribute");
+                    let (_, ratings) = pools.last_mut().expect("rating outside of a pool");
+                    ratings.push(Rating { controller, elo, matches });
+                }
+                Ok(Event::Eof) => break,
+                Err(e) => panic!("failed to parse ratings file: {}", e),
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        pools
+    }
+
+    fn create_rating_elem(rating: &Rating) -> BytesStart<'_> {
+        let mut rating_elem = BytesStart::owned(b"rating".to_vec(), "rating".len());
+
+        rating_elem.push_attribute(("controller", rating.controller.as_str()));
+        rating_elem.push_attribute(("elo", format!("{:.1}", rating.elo).as_str()));
+        rating_elem.push_attribute(("matches", rating.matches.to_string().as_str()));
+
+        rating_elem
+    }
+}
@@ -0,0 +1,504 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{BOT_MOVE_DELAY, FIELD_HEIGHT, FIELD_WIDTH};
+use crate::animation::Theme;
+use crate::direction::Direction;
+use crate::game_mode::GameMode;
+use crate::game_state::{GameState, PLAYER_INDEX};
+use crate::mutator::Mutator;
+use crate::puzzle::PuzzleLevel;
+use crate::ruleset::Ruleset;
+use crate::share_code::ShareCode;
+use crate::tron::{OPPONENT_INDEX, TronOpponent};
+
+/// First bytes of every replay file
+const MAGIC: &[u8; 4] = b"RSRP";
+/// Version of the file format, increased whenever the format or the game logic changes in a way
+/// that older replays would play out differently
+pub const REPLAY_VERSION: u8 = 2;
+/// Directory the replays of played games are saved to
+pub const REPLAY_DIRECTORY: &str = "replays";
+/// Most ticks a replay may hold, far more than it takes to fill the board. Replays are also decoded
+/// from share codes, so the count read must not be trusted to allocate the inputs.
+const MAX_TICK_COUNT: u64 = 10_000_000;
+/// Directions in the order of their codes in the file
+const DIRECTIONS: [Direction; 5] = [Direction::UP, Direction::DOWN, Direction::LEFT, Direction::RIGHT, Direction::STOP];
+
+/// Options of the `replay` and `replay share` commands, the replay is read from a file or decoded
+/// from a share code
+pub struct ReplayOptions {
+    pub(crate) file: Option<String>,
+    pub(crate) code: Option<String>,
+}
+
+impl ReplayOptions {
+    pub fn read_replay(&self) -> Result<Replay, String> {
+        match (&self.file, &self.code) {
+            (Some(file_path), _) => Replay::read(file_path),
+            (None, Some(code)) => match ShareCode::decode(code)? {
+                ShareCode::Replay(replay) => Ok(replay),
+                ShareCode::Challenge(_) => Err(String::from("the code is a challenge without moves, play it with --challenge")),
+            },
+            (None, None) => unreachable!("checked when parsing the options"),
+        }
+    }
+}
+
+/// Options of the `replay export` command
+pub struct ExportOptions {
+    pub(crate) file: String,
+    /// file the replay is written to as asciicast recording
+    pub(crate) asciicast: Option<String>,
+    /// file the replay is written to as animated SVG
+    pub(crate) svg: Option<String>,
+    /// file the replay is written to as animated GIF
+    pub(crate) gif: Option<String>,
+    /// size of a field of the SVG and GIF in pixels
+    pub(crate) cell_size: u16,
+    pub(crate) theme: Theme,
+    /// whether the SVG and GIF show score and time below the field
+    pub(crate) hud: bool,
+}
+
+/// Everything needed to play a game again: how it was set up and the directions of the snakes that
+/// are not controlled by the game on every tick. Since fruits are placed by the seeded rng, playing
+/// the inputs through the regular game logic leads to the exact same game.
+#[derive(Clone)]
+pub struct Replay {
+    pub(crate) seed: u64,
+    pub(crate) mode: GameMode,
+    pub(crate) tron_opponent: TronOpponent,
+    /// id of the level in puzzle mode
+    pub(crate) puzzle_level: Option<String>,
+    /// stored in full so that replays of custom rulesets stay playable when the rulesets file changes
+    pub(crate) ruleset: Ruleset,
+    pub(crate) mutators: Vec<Mutator>,
+    /// direction of the player and of the second player, STOP without one, on each tick
+    pub(crate) inputs: Vec<(Direction, Direction)>,
+    /// why the bot steering the player was disqualified, which ends the game after the last tick
+    pub(crate) disqualification: Option<String>,
+    /// score at the end of the game as it was recorded
+    pub(crate) score: u64,
+    /// time the game took in millis as it was recorded
+    pub(crate) time: u64,
+}
+
+impl Replay {
+    /// Starts recording the game, which must not have started yet
+    pub fn start(game_state: &GameState, ruleset: &Ruleset) -> Self {
+        Replay {
+            seed: game_state.seed,
+            mode: game_state.mode,
+            tron_opponent: game_state.tron_opponent,
+            puzzle_level: game_state.puzzle_level.as_ref().map(|level| String::from(level.id)),
+            ruleset: ruleset.clone(),
+            mutators: game_state.mutators.clone(),
+            inputs: Vec::new(),
+            disqualification: None,
+            score: 0,
+            time: 0,
+        }
+    }
+
+    /// Records the directions the snakes are about to move in, call it right before the game
+    /// advances. Nothing is recorded while the player pauses since the game does not advance.
+    pub fn record(&mut self, game_state: &GameState) {
+        if game_state.player().direction == Direction::STOP {
+            return;
+        }
+
+        let second_direction = if game_state.tron_opponent == TronOpponent::LOCAL {
+            game_state.snakes[OPPONENT_INDEX].direction
+        } else {
+            Direction::STOP
+        };
+        self.inputs.push((game_state.player().direction, second_direction));
+    }
+
+    /// Records the result of the game once it is over
+    pub fn finish(&mut self, game_state: &GameState, time: u64, disqualification: Option<&str>) {
+        self.score = game_state.current_score;
+        self.time = time;
+        self.disqualification = disqualification.map(String::from);
+    }
+
+    /// Returns the game in the state it was before the first tick
+    pub fn create_game_state(&self) -> GameState {
+        let mut game_state = match (self.mode, &self.puzzle_level) {
+            (GameMode::PUZZLE, Some(level_id)) => {
+                let level = find_puzzle_level(level_id).expect("puzzle level checked when reading the replay");
+                GameState::new_puzzle(level.clone())
+            }
+            (GameMode::TRON, _) => GameState::new_tron(self.tron_opponent, self.mutators.clone()),
+            (mode, _) => GameState::new(mode, self.mutators.clone()),
+        };
+        game_state.reseed(self.seed);
+
+        game_state
+    }
+
+    /// Plays the recorded tick with the provided index, the game ends after the last tick if the
+    /// bot steering the player was disqualified. Ticks after the game is over are ignored, crafted
+    /// replays may hold more of them than the game had.
+    pub fn play_tick(&self, game_state: &mut GameState, tick: usize) {
+        if game_state.game_over {
+            return;
+        }
+
+        let (direction, second_direction) = self.inputs[tick];
+        game_state.player_mut().direction = direction;
+        if game_state.tron_opponent == TronOpponent::LOCAL {
+            game_state.snakes[OPPONENT_INDEX].direction = second_direction;
+        }
+        game_state.handle_snake_movement(&self.ruleset);
+
+        if tick + 1 == self.inputs.len() && self.disqualification.is_some() {
+            game_state.disqualify(PLAYER_INDEX);
+        }
+    }
+
+    /// Returns the time between two ticks in millis when playing the replay back, turn-based games
+    /// are played at the pace of bots
+    pub fn get_tick_delay(&self) -> u64 {
+        if self.mode == GameMode::PUZZLE {
+            BOT_MOVE_DELAY
+        } else {
+            self.ruleset.get_refresh_delay()
+        }
+    }
+
+    /// Plays all recorded ticks without rendering and returns the game as it ended
+    pub fn simulate(&self) -> GameState {
+        let mut game_state = self.create_game_state();
+        for tick in 0..self.inputs.len() {
+            self.play_tick(&mut game_state, tick);
+        }
+
+        game_state
+    }
+
+    /// Saves the replay of a finished game to the replay directory and returns its path, the name
+    /// holds the time the game ended, the mode, the ruleset and the score
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let ended = SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_millis()).unwrap_or(0);
+        let ruleset_id: String = self.ruleset.id.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+            .collect();
+        let file_path = Path::new(REPLAY_DIRECTORY).join(format!("{}-{}-{}-{}.replay", ended, self.mode, ruleset_id, self.score));
+        self.write(&file_path)?;
+
+        Ok(file_path)
+    }
+
+    pub fn write(&self, file_path: &Path) -> Result<(), String> {
+        if let Some(directory) = file_path.parent() {
+            fs::create_dir_all(directory).map_err(|error| format!("could not create '{}': {}", directory.display(), error))?;
+        }
+        fs::write(file_path, self.to_bytes()).map_err(|error| format!("could not write '{}': {}", file_path.display(), error))
+    }
+
+    pub fn read(file_path: &str) -> Result<Replay, String> {
+        let bytes = fs::read(file_path).map_err(|error| format!("could not read '{}': {}", file_path, error))?;
+        Self::from_bytes(&bytes).map_err(|error| format!("invalid replay '{}': {}", file_path, error))
+    }
+
+    /// Encodes the replay, runs of ticks with the same directions are stored as a single entry
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(REPLAY_VERSION);
+        bytes.extend_from_slice(&FIELD_WIDTH.to_le_bytes());
+        bytes.extend_from_slice(&FIELD_HEIGHT.to_le_bytes());
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        push_string(&mut bytes, &self.mode.to_string());
+        push_string(&mut bytes, &self.tron_opponent.to_string());
+        push_string(&mut bytes, self.puzzle_level.as_deref().unwrap_or(""));
+
+        push_string(&mut bytes, &self.ruleset.id);
+        push_string(&mut bytes, &self.ruleset.name);
+        bytes.push(self.ruleset.wall_wrap as u8);
+        push_varint(&mut bytes, self.ruleset.refresh_delay);
+        push_varint(&mut bytes, self.ruleset.growth_per_fruit as u64);
+        push_varint(&mut bytes, self.ruleset.score_multiplier as u64);
+
+        bytes.push(self.mutators.len() as u8);
+        for mutator in self.mutators.iter() {
+            push_string(&mut bytes, mutator.get_id());
+        }
+
+        push_varint(&mut bytes, self.score);
+        push_varint(&mut bytes, self.time);
+        push_string(&mut bytes, self.disqualification.as_deref().unwrap_or(""));
+
+        push_varint(&mut bytes, self.inputs.len() as u64);
+        let mut runs: Vec<(u8, u64)> = Vec::new();
+        for (direction, second_direction) in self.inputs.iter() {
+            let code = get_direction_code(*direction) | get_direction_code(*second_direction) << 4;
+            match runs.last_mut() {
+                Some((run_code, length)) if *run_code == code => *length += 1,
+                _ => runs.push((code, 1)),
+            }
+        }
+        for (code, length) in runs {
+            bytes.push(code);
+            push_varint(&mut bytes, length);
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, String> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.read_bytes(MAGIC.len())? != MAGIC {
+            return Err(String::from("not a replay file"));
+        }
+        let version = reader.read_u8()?;
+        if version != REPLAY_VERSION {
+            return Err(format!("recorded with version {} of the format, this build plays version {}", version, REPLAY_VERSION));
+        }
+        let width = u16::from_le_bytes([reader.read_u8()?, reader.read_u8()?]);
+        let height = u16::from_le_bytes([reader.read_u8()?, reader.read_u8()?]);
+        if (width, height) != (FIELD_WIDTH, FIELD_HEIGHT) {
+            return Err(format!("recorded on a {}x{} board, this build plays on {}x{}", width, height, FIELD_WIDTH, FIELD_HEIGHT));
+        }
+        let mut seed_bytes = [0; 8];
+        seed_bytes.copy_from_slice(reader.read_bytes(8)?);
+        let seed = u64::from_le_bytes(seed_bytes);
+
+        let mode_name = reader.read_string()?;
+        let mode = GameMode::all().iter().copied().find(|mode| mode.to_string() == mode_name)
+            .ok_or(format!("unknown mode '{}'", mode_name))?;
+        let opponent_name = reader.read_string()?;
+        let tron_opponent = TronOpponent::all().iter().copied().find(|opponent| opponent.to_string() == opponent_name)
+            .ok_or(format!("unknown opponent '{}'", opponent_name))?;
+        let puzzle_level = Some(reader.read_string()?).filter(|level_id| !level_id.is_empty());
+        match &puzzle_level {
+            Some(level_id) if find_puzzle_level(level_id).is_none() => return Err(format!("unknown puzzle level '{}'", level_id)),
+            None if mode == GameMode::PUZZLE => return Err(String::from("puzzle level is missing")),
+            _ => {}
+        }
+
+        let id = reader.read_string()?;
+        let name = reader.read_string()?;
+        let ruleset = Ruleset {
+            id,
+            name,
+            description: String::new(),
+            wall_wrap: reader.read_u8()? != 0,
+            refresh_delay: reader.read_varint()?,
+            growth_per_fruit: reader.read_varint()? as u16,
+            score_multiplier: reader.read_varint()? as u16,
+        };
+
+        let mutator_count = reader.read_u8()?;
+        let mut mutators = Vec::new();
+        for _ in 0..mutator_count {
+            let mutator_id = reader.read_string()?;
+            mutators.push(Mutator::from_id(&mutator_id).ok_or(format!("unknown mutator '{}'", mutator_id))?);
+        }
+        Mutator::check_mode(&mutators, mode)?;
+
+        let score = reader.read_varint()?;
+        let time = reader.read_varint()?;
+        let disqualification = Some(reader.read_string()?).filter(|reason| !reason.is_empty());
+
+        let tick_count = reader.read_varint()?;
+        if tick_count > MAX_TICK_COUNT {
+            return Err(format!("{} ticks are more than the {} a replay may hold", tick_count, MAX_TICK_COUNT));
+        }
+        let mut inputs = Vec::new();
+        while (inputs.len() as u64) < tick_count {
+            let code = reader.read_u8()?;
+            let direction = get_direction(code & 0x0F)?;
+            let second_direction = get_direction(code >> 4)?;
+            let length = reader.read_varint()?;
+            if length > tick_count - inputs.len() as u64 {
+                return Err(String::from("more inputs than ticks"));
+            }
+            inputs.extend((0..length).map(|_| (direction, second_direction)));
+        }
+        if reader.position != bytes.len() {
+            return Err(String::from("unexpected data after the inputs"));
+        }
+
+        Ok(Replay {
+            seed,
+            mode,
+            tron_opponent,
+            puzzle_level,
+            ruleset,
+            mutators,
+            inputs,
+            disqualification,
+            score,
+            time,
+        })
+    }
+}
+
+pub(crate) fn find_puzzle_level(level_id: &str) -> Option<&'static PuzzleLevel> {
+    PuzzleLevel::built_in().iter().find(|level| level.id == level_id)
+}
+
+fn get_direction_code(direction: Direction) -> u8 {
+    DIRECTIONS.iter().position(|other| *other == direction).expect("all directions have a code") as u8
+}
+
+fn get_direction(code: u8) -> Result<Direction, String> {
+    DIRECTIONS.get(code as usize).copied().ok_or(format!("unknown direction code {}", code))
+}
+
+/// Appends the number in LEB128 encoding: seven bits per byte, the highest bit is set on all bytes
+/// but the last
+pub(crate) fn push_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+pub(crate) fn push_string(bytes: &mut Vec<u8>, value: &str) {
+    push_varint(bytes, value.len() as u64);
+    bytes.extend_from_slice(value.as_bytes());
+}
+
+pub(crate) struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pub(crate) position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        ByteReader { bytes, position: 0 }
+    }
+
+    pub(crate) fn read_bytes(&mut self, length: usize) -> Result<&'a [u8], String> {
+        if length > self.bytes.len() - self.position {
+            return Err(String::from("file is truncated"));
+        }
+        self.position += length;
+
+        Ok(&self.bytes[self.position - length..self.position])
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Result<u8, String> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub(crate) fn read_varint(&mut self) -> Result<u64, String> {
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(String::from("number out of range"))
+    }
+
+    pub(crate) fn read_string(&mut self) -> Result<String, String> {
+        let length = self.read_varint()?;
+        if length > (self.bytes.len() - self.position) as u64 {
+            return Err(String::from("file is truncated"));
+        }
+        let bytes = self.read_bytes(length as usize)?;
+
+        String::from_utf8(bytes.to_vec()).map_err(|_| String::from("text is not valid UTF-8"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autopilot::AutopilotController;
+    use crate::controller::{Controller, Observation};
+    use crate::share_code::Challenge;
+
+    /// Plays a seeded game with the autopilot for at most the number of ticks, returns the game and
+    /// its replay
+    fn play_autopilot_game(seed: u64, mutators: Vec<Mutator>, max_ticks: usize) -> (GameState, Replay) {
+        let ruleset = Ruleset::built_in().into_iter().find(|ruleset| ruleset.id == "NORMAL").expect("NORMAL is built in");
+        let mut game_state = GameState::new(GameMode::CLASSIC, mutators);
+        game_state.reseed(seed);
+        let mut replay = Replay::start(&game_state, &ruleset);
+        let mut controller = AutopilotController;
+
+        for _ in 0..max_ticks {
+            if game_state.game_over {
+                break;
+            }
+            game_state.player_mut().direction = controller.next_direction(&Observation::new(&game_state, &ruleset, PLAYER_INDEX));
+            replay.record(&game_state);
+            game_state.handle_snake_movement(&ruleset);
+        }
+        replay.finish(&game_state, 0, None);
+
+        (game_state, replay)
+    }
+
+    #[test]
+    fn decoded_replay_plays_out_the_same() {
+        // drifting fruit moves with the random number generator, so it only ends up in the same
+        // places if the game is simulated exactly
+        let (game_state, replay) = play_autopilot_game(7, vec![Mutator::DriftingFruit, Mutator::DoubleGrowth], 1000);
+        assert!(game_state.current_score > 0);
+
+        let decoded = Replay::from_bytes(&replay.to_bytes()).expect("encoded replay decodes");
+        let simulated = decoded.simulate();
+        assert_eq!(decoded.score, game_state.current_score);
+        assert_eq!(simulated.current_score, game_state.current_score);
+        assert_eq!(simulated.player().tail_x_pos.len(), game_state.player().tail_x_pos.len());
+    }
+
+    #[test]
+    fn ticks_after_the_game_is_over_are_ignored() {
+        let level = find_puzzle_level("L1").expect("L1 is built in");
+        let ruleset = level.to_ruleset();
+        let mut game_state = GameState::new_puzzle(level.clone());
+        let mut replay = Replay::start(&game_state, &ruleset);
+        // the fruits of the level are five fields up, five to the right and five down in a row
+        for direction in [Direction::UP, Direction::RIGHT, Direction::DOWN] {
+            for _ in 0..5 {
+                game_state.player_mut().direction = direction;
+                replay.record(&game_state);
+                game_state.handle_snake_movement(&ruleset);
+            }
+        }
+        assert!(game_state.won);
+        replay.finish(&game_state, 0, None);
+
+        // a crafted replay carrying on after the win must neither add the bonus again nor overrun the budget
+        replay.inputs.extend([(Direction::LEFT, Direction::STOP); 10]);
+        let simulated = Replay::from_bytes(&replay.to_bytes()).expect("replay with trailing ticks decodes").simulate();
+        assert!(simulated.won);
+        assert_eq!(simulated.tick_count, game_state.tick_count);
+        assert_eq!(simulated.current_score, game_state.current_score);
+    }
+
+    #[test]
+    fn share_codes_decode_to_what_was_encoded() {
+        let (_, replay) = play_autopilot_game(3, vec![Mutator::FogOfWar], 300);
+
+        // long codes are often wrapped when pasted
+        let code = ShareCode::Replay(replay.clone()).encode();
+        let wrapped_code = code.as_bytes().chunks(40).map(|line| String::from_utf8_lossy(line)).collect::<Vec<_>>().join("\n");
+        match ShareCode::decode(&wrapped_code).expect("replay code decodes") {
+            ShareCode::Replay(decoded) => assert_eq!(decoded.to_bytes(), replay.to_bytes()),
+            ShareCode::Challenge(_) => panic!("replay code decoded to a challenge"),
+        }
+
+        let code = ShareCode::Challenge(Challenge::from_replay(&replay)).encode();
+        let challenge = ShareCode::decode(&code).expect("challenge code decodes").into_challenge();
+        assert_eq!(challenge.seed, replay.seed);
+        assert_eq!(challenge.mode, replay.mode);
+        assert_eq!(challenge.ruleset_id, replay.ruleset.id);
+        assert_eq!(challenge.mutators, replay.mutators);
+        let first_fruit = |game_state: &GameState| (game_state.fruits[0].x_pos, game_state.fruits[0].y_pos);
+        assert_eq!(first_fruit(&challenge.create_game_state()), first_fruit(&replay.create_game_state()));
+    }
+}
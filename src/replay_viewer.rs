@@ -0,0 +1,206 @@
+use std::time::Duration;
+
+use pancurses::{Input, Window};
+
+use crate::{render_frame, FIELD_WIDTH, FRUIT_SYMBOL, QUIT_KEY};
+use crate::game_state::GameState;
+use crate::replay::Replay;
+
+const PLAY_KEY: char = ' ';
+const STEP_BACK_KEY: char = ',';
+const STEP_FORWARD_KEY: char = '.';
+const SLOWER_KEY: char = '-';
+const FASTER_KEY: char = '+';
+const JUMP_KEY: char = 'g';
+const ESCAPE_KEY: char = '\u{1b}';
+
+/// Playback speeds as multiples of the recorded speed
+const SPEEDS: [f64; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+const DEFAULT_SPEED_INDEX: usize = 2;
+
+/// Number of ticks between the saved game states seeking starts from, so stepping back does not
+/// have to play the replay from the beginning
+const CHECKPOINT_INTERVAL: usize = 100;
+
+/// Plays a replay with the controls of a video player: pausing, stepping a tick at a time in both
+/// directions, changing the speed and jumping to a tick
+struct ReplayViewer<'a> {
+    replay: &'a Replay,
+    /// game states at every multiple of the checkpoint interval
+    checkpoints: Vec<GameState>,
+    /// ticks on which the player ate a fruit
+    pickups: Vec<usize>,
+    game_state: GameState,
+    /// number of ticks played to reach the current game state
+    tick: usize,
+    speed_index: usize,
+    playing: bool,
+    /// digits typed after the jump key, until confirmed or cancelled
+    jump_input: Option<String>,
+}
+
+impl<'a> ReplayViewer<'a> {
+    fn new(replay: &'a Replay) -> Self {
+        let mut game_state = replay.create_game_state();
+        let mut checkpoints = vec![game_state.clone()];
+        let mut pickups = Vec::new();
+        for tick in 0..replay.inputs.len() {
+            let score = game_state.current_score;
+            replay.play_tick(&mut game_state, tick);
+            // the player scores by eating fruits only
+            if game_state.current_score > score {
+                pickups.push(tick + 1);
+            }
+            if (tick + 1) % CHECKPOINT_INTERVAL == 0 {
+                checkpoints.push(game_state.clone());
+            }
+        }
+
+        ReplayViewer {
+            replay,
+            game_state: checkpoints[0].clone(),
+            checkpoints,
+            pickups,
+            tick: 0,
+            speed_index: DEFAULT_SPEED_INDEX,
+            playing: true,
+            jump_input: None,
+        }
+    }
+
+    fn get_tick_count(&self) -> usize {
+        self.replay.inputs.len()
+    }
+
+    fn step_forward(&mut self) {
+        if self.tick < self.get_tick_count() {
+            self.replay.play_tick(&mut self.game_state, self.tick);
+            self.tick += 1;
+        }
+    }
+
+    /// Continues from the closest checkpoint before the tick, ticks past the end show the end
+    fn seek(&mut self, tick: usize) {
+        let tick = tick.min(self.get_tick_count());
+        let checkpoint = tick / CHECKPOINT_INTERVAL;
+        self.game_state = self.checkpoints[checkpoint].clone();
+        self.tick = checkpoint * CHECKPOINT_INTERVAL;
+        while self.tick < tick {
+            self.step_forward();
+        }
+    }
+
+    /// Handles a key, returns false once the viewer should be closed
+    fn handle_key(&mut self, input: Input) -> bool {
+        if let Some(jump_input) = &mut self.jump_input {
+            match input {
+                Input::Character(c) if c.is_ascii_digit() => jump_input.push(c),
+                Input::Character('\u{8}') | Input::Character('\u{7f}') | Input::KeyBackspace => {
+                    jump_input.pop();
+                }
+                Input::Character('\n') | Input::KeyEnter => {
+                    if let Ok(tick) = jump_input.parse() {
+                        self.seek(tick);
+                    }
+                    self.jump_input = None;
+                }
+                Input::Character(ESCAPE_KEY) => self.jump_input = None,
+                _ => {}
+            }
+            return true;
+        }
+
+        match input {
+            Input::Character(QUIT_KEY) => return false,
+            Input::Character(PLAY_KEY) => {
+                // playing from the end starts over
+                if !self.playing && self.tick == self.get_tick_count() {
+                    self.seek(0);
+                }
+                self.playing = !self.playing;
+            }
+            Input::Character(STEP_FORWARD_KEY) | Input::KeyRight => {
+                self.playing = false;
+                self.step_forward();
+            }
+            Input::Character(STEP_BACK_KEY) | Input::KeyLeft => {
+                self.playing = false;
+                self.seek(self.tick.saturating_sub(1));
+            }
+            Input::Character(SLOWER_KEY) | Input::KeyDown => self.speed_index = self.speed_index.saturating_sub(1),
+            Input::Character(FASTER_KEY) | Input::Character('=') | Input::KeyUp => {
+                self.speed_index = (self.speed_index + 1).min(SPEEDS.len() - 1);
+            }
+            Input::Character(JUMP_KEY) => {
+                self.playing = false;
+                self.jump_input = Some(String::new());
+            }
+            _ => {}
+        }
+
+        true
+    }
+
+    /// Returns the time in millis until the next tick is played
+    fn get_playback_delay(&self) -> i32 {
+        (self.replay.get_tick_delay() as f64 / SPEEDS[self.speed_index]).round().max(1.0) as i32
+    }
+
+    /// Returns a bar for the whole replay, marking fruit pickups and the current tick
+    fn render_timeline(&self) -> String {
+        let width = FIELD_WIDTH as usize - 2;
+        let column = |tick: usize| tick * (width - 1) / self.get_tick_count().max(1);
+        let mut bar = vec!['-'; width];
+        for tick in self.pickups.iter() {
+            bar[column(*tick)] = FRUIT_SYMBOL;
+        }
+        bar[column(self.tick)] = '|';
+
+        format!("[{}]", bar.into_iter().collect::<String>())
+    }
+
+    fn draw(&self, window: &Window) {
+        let elapsed = Duration::from_millis(self.tick as u64 * self.replay.get_tick_delay());
+        window.clear();
+        window.addstr(render_frame(&self.game_state, "-", &self.replay.ruleset, elapsed, None));
+        window.addstr("\n\n");
+        window.addstr(self.render_timeline());
+        window.addstr(format!(
+            "\nREPLAY - tick {}/{} - {}x - {}\n",
+            self.tick, self.get_tick_count(), SPEEDS[self.speed_index],
+            if self.playing { "playing" } else if self.tick == self.get_tick_count() { "end" } else { "paused" },
+        ));
+        match &self.jump_input {
+            Some(jump_input) => window.addstr(format!("Jump to tick: {}_ (enter to jump, esc to cancel)", jump_input)),
+            None => window.addstr("space play/pause, , . step, - + speed, g jump to tick, q quit"),
+        };
+        window.refresh();
+    }
+}
+
+/// Shows the replay in the viewer until it is closed with q
+pub fn run(window: &Window, replay: &Replay) {
+    let mut viewer = ReplayViewer::new(replay);
+    window.keypad(true);
+
+    loop {
+        viewer.draw(window);
+        window.timeout(if viewer.playing { viewer.get_playback_delay() } else { -1 });
+        match window.getch() {
+            Some(input) => {
+                if !viewer.handle_key(input) {
+                    break;
+                }
+            }
+            None => {
+                viewer.step_forward();
+                if viewer.tick == viewer.get_tick_count() {
+                    viewer.playing = false;
+                }
+            }
+        }
+    }
+
+    window.timeout(-1);
+    window.keypad(false);
+}
@@ -0,0 +1,132 @@
+use std::fmt;
+use std::path::Path;
+
+use quick_xml::events::attributes::Attribute;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+/// A set of rules a game is played with. The built-in presets replace the former hardcoded
+/// difficulties and keep their names as ids so existing high scores stay attached to them, custom
+/// rulesets may be defined in the rulesets file and get their own leaderboard keyed by their id.
+#[derive(Debug, Clone)]
+pub struct Ruleset {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) wall_wrap: bool,
+    pub(crate) refresh_delay: u64,
+    pub(crate) growth_per_fruit: u16,
+    pub(crate) score_multiplier: u16,
+}
+
+impl fmt::Display for Ruleset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl Ruleset {
+    fn preset(id: &str, description: &str, wall_wrap: bool, refresh_delay: u64, score_multiplier: u16) -> Ruleset {
+        Ruleset {
+            id: String::from(id),
+            name: String::from(id),
+            description: String::from(description),
+            wall_wrap,
+            refresh_delay,
+            growth_per_fruit: 1,
+            score_multiplier,
+        }
+    }
+
+    /// Returns the built-in presets in menu order
+    pub fn built_in() -> Vec<Ruleset> {
+        vec![
+            Self::preset("EASY", "No game over on wall collision and slow speed", true, 150, 1),
+            Self::preset("ARCADE", "Easy but speedy", true, 50, 2),
+            Self::preset("NORMAL", "Game over on wall collision, normal speed", false, 150, 3),
+            Self::preset("HARD", "Dangerous walls and fast speed", false, 50, 5),
+        ]
+    }
+
+    /// Returns the built-in presets followed by the custom rulesets defined in the provided file,
+    /// the file is optional and only the presets are returned if it does not exist
+    pub fn load_all(file_path: &str) -> Vec<Ruleset> {
+        let mut rulesets = Self::built_in();
+        let path = Path::new(file_path);
+        if !path.exists() {
+            return rulesets;
+        }
+
+        let mut xml_reader = Reader::from_file(path).expect("failed to initialize xml reader");
+        let mut buf = Vec::new();
+
+        loop {
+            match xml_reader.read_event(&mut buf) {
+                Ok(Event::Empty(ref elem)) | Ok(Event::Start(ref elem)) if elem.name() == b"ruleset" => {
+                    let mut id: Option<String> = None;
+                    let mut name: Option<String> = None;
+                    let mut ruleset = Self::preset("", "", false, 150, 1);
+
+                    for attr in elem.attributes() {
+                        let attribute: Attribute = attr.expect("could not read attribute");
+                        let value = attribute.unescape_and_decode_value(&xml_reader).expect("could not decode attribute");
+
+                        match attribute.key {
+                            b"id" => id = Some(value),
+                            b"name" => name = Some(value),
+                            b"description" => ruleset.description = value,
+                            b"wall-wrap" => ruleset.wall_wrap = value.parse().expect("could not parse value of attribute wall-wrap as bool"),
+                            b"refresh-delay" => ruleset.refresh_delay = value.parse().expect("could not parse value of attribute refresh-delay as u64"),
+                            b"growth-per-fruit" => ruleset.growth_per_fruit = value.parse().expect("could not parse value of attribute growth-per-fruit as u16"),
+                            b"score-multiplier" => ruleset.score_multiplier = value.parse().expect("could not parse value of attribute score-multiplier as u16"),
+                            _ => {}
+                        }
+                    }
+
+                    let id = id.expect("ruleset is missing the id attribute");
+                    if rulesets.iter().any(|existing| existing.id == id) {
+                        panic!("duplicate ruleset id {}", id);
+                    }
+                    ruleset.name = name.unwrap_or_else(|| id.clone());
+                    ruleset.id = id;
+                    rulesets.push(ruleset);
+                }
+                Ok(Event::Eof) => break,
+                Err(e) => panic!("failed to parse rulesets file: {}", e),
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        rulesets
+    }
+
+    /// Returns true if games played with the other ruleset follow the same rules, the name and the
+    /// description do not matter
+    pub(crate) fn has_same_rules(&self, other: &Ruleset) -> bool {
+        self.wall_wrap == other.wall_wrap
+            && self.refresh_delay == other.refresh_delay
+            && self.growth_per_fruit == other.growth_per_fruit
+            && self.score_multiplier == other.score_multiplier
+    }
+
+    pub(crate) fn is_game_over_on_wall_collision(&self) -> bool {
+        !self.wall_wrap
+    }
+
+    pub(crate) fn get_refresh_delay(&self) -> u64 {
+        self.refresh_delay
+    }
+
+    pub(crate) fn get_description(&self) -> &str {
+        &self.description
+    }
+
+    pub(crate) fn get_growth_per_fruit(&self) -> u16 {
+        self.growth_per_fruit
+    }
+
+    pub(crate) fn get_score_multiplier(&self) -> u16 {
+        self.score_multiplier
+    }
+}
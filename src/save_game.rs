@@ -0,0 +1,272 @@
+use std::fs::{self, File};
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+
+use quick_xml::{Reader, Writer};
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
+
+use crate::{FIELD_HEIGHT, FIELD_WIDTH};
+use crate::direction::Direction;
+use crate::fruit::Fruit;
+use crate::game_state::GameState;
+use crate::replay::Replay;
+use crate::splits::Splits;
+
+/// Directory the save games are kept in, each user has a save game of their own. The moves made so
+/// far are kept as a replay next to the save game, so resumed games can still be verified.
+const SAVE_GAME_DIRECTORY: &str = "savegames";
+
+/// Number of ticks between automatic saves of real-time games, turn-based games are saved after
+/// every move
+pub const AUTOSAVE_INTERVAL: u64 = 50;
+
+const DIRECTIONS: [Direction; 5] = [Direction::UP, Direction::DOWN, Direction::LEFT, Direction::RIGHT, Direction::STOP];
+
+/// A game in progress as it was last saved: the setup and the moves so far as replay, the positions
+/// of the snakes and fruits, the score, the time played and the state of the rng
+pub struct SaveGame {
+    pub(crate) user: String,
+    pub(crate) replay: Replay,
+    pub(crate) game_state: GameState,
+    /// time played so far in millis
+    pub(crate) time: u64,
+    /// whether hints were shown so far
+    pub(crate) hints: bool,
+    pub(crate) splits: Splits,
+}
+
+impl SaveGame {
+    /// Saves the game of the user before its next tick, the replay holds all ticks played so far
+    pub fn write(user: &str, game_state: &GameState, replay: &Replay, time: u64, hints: bool, splits: &Splits) -> Result<(), String> {
+        let (file_path, replay_file_path) = get_file_paths(user);
+        // also creates the directory
+        replay.write(&replay_file_path)?;
+
+        let bytes = Self::to_xml(user, game_state, time, hints, splits);
+        File::create(&file_path)
+            .and_then(|mut file| file.write_all(bytes.as_slice()))
+            .map_err(|error| format!("could not write '{}': {}", file_path.display(), error))
+    }
+
+    /// Returns the saved game of the user, None if there is none
+    pub fn read(user: &str) -> Result<Option<SaveGame>, String> {
+        let (file_path, replay_file_path) = get_file_paths(user);
+        if !file_path.exists() {
+            return Ok(None);
+        }
+
+        let replay = Replay::read(&replay_file_path.to_string_lossy())?;
+        let bytes = fs::read(&file_path).map_err(|error| format!("could not read '{}': {}", file_path.display(), error))?;
+        let save_game = Self::from_xml(replay, &bytes)
+            .map_err(|message| format!("invalid save game '{}': {}", file_path.display(), message))?;
+
+        // names differing only in characters not allowed in file names share the file
+        Ok(Some(save_game).filter(|save_game| save_game.user == user))
+    }
+
+    fn to_xml(user: &str, game_state: &GameState, time: u64, hints: bool, splits: &Splits) -> Vec<u8> {
+        let mut writer = Writer::new_with_indent(Cursor::new(Vec::new()), b' ', 2);
+        writer.write_event(Event::Decl(BytesDecl::new(b"1.0", Some(b"UTF-8"), None))).expect("failed to write elem");
+        let mut save_game_elem = BytesStart::owned(b"savegame".to_vec(), "savegame".len());
+        save_game_elem.push_attribute(("user", user));
+        save_game_elem.push_attribute(("score", game_state.current_score.to_string().as_str()));
+        save_game_elem.push_attribute(("ticks", game_state.tick_count.to_string().as_str()));
+        save_game_elem.push_attribute(("time", time.to_string().as_str()));
+        save_game_elem.push_attribute(("rng", game_state.rng.state.to_string().as_str()));
+        save_game_elem.push_attribute(("wall-inset", game_state.wall_inset.to_string().as_str()));
+        if hints {
+            save_game_elem.push_attribute(("hints", "true"));
+        }
+        if !splits.times.is_empty() {
+            save_game_elem.push_attribute(("splits", splits.to_string().as_str()));
+        }
+        writer.write_event(Event::Start(save_game_elem)).expect("failed to write elem");
+
+        for snake in game_state.snakes.iter() {
+            let mut snake_elem = BytesStart::owned(b"snake".to_vec(), "snake".len());
+            snake_elem.push_attribute(("x", snake.x_pos.to_string().as_str()));
+            snake_elem.push_attribute(("y", snake.y_pos.to_string().as_str()));
+            snake_elem.push_attribute(("direction", snake.direction.to_string().as_str()));
+            snake_elem.push_attribute(("alive", snake.alive.to_string().as_str()));
+            // elements of the tail that have not entered the field yet are stored as growth left
+            let tail: Vec<String> = snake.tail_x_pos.iter().zip(snake.tail_y_pos.iter())
+                .filter(|(x, y)| **x < FIELD_WIDTH && **y < FIELD_HEIGHT)
+                .map(|(x, y)| format!("{},{}", x, y))
+                .collect();
+            snake_elem.push_attribute(("tail", tail.join(" ").as_str()));
+            snake_elem.push_attribute(("growth", (snake.tail_x_pos.len() - tail.len()).to_string().as_str()));
+            writer.write_event(Event::Empty(snake_elem)).expect("failed to write elem");
+        }
+        for fruit in game_state.fruits.iter() {
+            let mut fruit_elem = BytesStart::owned(b"fruit".to_vec(), "fruit".len());
+            fruit_elem.push_attribute(("x", fruit.x_pos.to_string().as_str()));
+            fruit_elem.push_attribute(("y", fruit.y_pos.to_string().as_str()));
+            writer.write_event(Event::Empty(fruit_elem)).expect("failed to write elem");
+        }
+        writer.write_event(Event::End(BytesEnd::borrowed(b"savegame"))).expect("failed to write elem");
+
+        writer.into_inner().into_inner()
+    }
+
+    /// Restores the game from the save game and the replay of the moves so far
+    fn from_xml(replay: Replay, bytes: &[u8]) -> Result<SaveGame, String> {
+        let mut game_state = replay.create_game_state();
+        let snake_count = game_state.snakes.len();
+        game_state.fruits.clear();
+        let mut user = None;
+        let mut time = 0;
+        let mut hints = false;
+        let mut splits = Splits::default();
+        let mut snakes_read = 0;
+
+        let mut xml_reader = Reader::from_reader(bytes);
+        let mut buf = Vec::new();
+        loop {
+            let elem = match xml_reader.read_event(&mut buf) {
+                Ok(Event::Start(elem)) | Ok(Event::Empty(elem)) => elem,
+                Ok(Event::Eof) => break,
+                Ok(_) => continue,
+                Err(error) => return Err(error.to_string()),
+            };
+            let mut attributes = Vec::new();
+            for attr in elem.attributes() {
+                let attribute = attr.map_err(|error| error.to_string())?;
+                let value = attribute.unescape_and_decode_value(&xml_reader).map_err(|error| error.to_string())?;
+                attributes.push((String::from_utf8_lossy(attribute.key).into_owned(), value));
+            }
+            let get = |key: &str| {
+                attributes.iter()
+                    .find(|(other_key, _)| other_key == key)
+                    .map(|(_, value)| value.as_str())
+                    .ok_or_else(|| format!("{} is missing the {} attribute", String::from_utf8_lossy(elem.name()), key))
+            };
+
+            match elem.name() {
+                b"savegame" => {
+                    user = Some(String::from(get("user")?));
+                    game_state.current_score = parse(get("score")?)?;
+                    game_state.tick_count = parse(get("ticks")?)?;
+                    time = parse(get("time")?)?;
+                    game_state.rng.state = parse(get("rng")?)?;
+                    game_state.wall_inset = parse(get("wall-inset")?)?;
+                    hints = get("hints").is_ok_and(|value| value == "true");
+                    if let Ok(value) = get("splits") {
+                        splits = Splits::parse(value).ok_or_else(|| format!("could not parse '{}'", value))?;
+                    }
+                }
+                b"snake" => {
+                    if snakes_read == snake_count {
+                        return Err(format!("{} snakes play in this mode", snake_count));
+                    }
+                    let snake = &mut game_state.snakes[snakes_read];
+                    (snake.x_pos, snake.y_pos) = parse_position(get("x")?, get("y")?)?;
+                    snake.direction = DIRECTIONS.iter().copied()
+                        .find(|direction| direction.to_string() == get("direction").unwrap_or_default())
+                        .ok_or_else(|| format!("unknown direction '{}'", get("direction").unwrap_or_default()))?;
+                    snake.alive = parse(get("alive")?)?;
+                    snake.tail_x_pos.clear();
+                    snake.tail_y_pos.clear();
+                    for field in get("tail")?.split_whitespace() {
+                        let (x, y) = field.split_once(',').ok_or_else(|| format!("'{}' is not a position", field))?;
+                        let (x, y) = parse_position(x, y)?;
+                        snake.tail_x_pos.push(x);
+                        snake.tail_y_pos.push(y);
+                    }
+                    let growth: usize = get("growth").map_or(Ok(0), parse)?;
+                    if growth > (FIELD_WIDTH * FIELD_HEIGHT) as usize {
+                        return Err(format!("a tail growing by {} is longer than the field", growth));
+                    }
+                    for _ in 0..growth {
+                        snake.append_tail();
+                    }
+                    snakes_read += 1;
+                }
+                b"fruit" => {
+                    let (x_pos, y_pos) = parse_position(get("x")?, get("y")?)?;
+                    game_state.fruits.push(Fruit { x_pos, y_pos });
+                }
+                _ => {}
+            }
+        }
+
+        if snakes_read != snake_count {
+            return Err(format!("{} snakes play in this mode", snake_count));
+        }
+        let user = user.ok_or_else(|| String::from("savegame element is missing"))?;
+
+        Ok(SaveGame { user, replay, game_state, time, hints, splits })
+    }
+
+    /// Removes the saved game of the user once it has been finished. A save game that cannot be
+    /// removed is offered to be resumed again, which is no reason to lose the score of the game that
+    /// just ended.
+    pub fn delete(user: &str) {
+        let (file_path, replay_file_path) = get_file_paths(user);
+        for file_path in [file_path, replay_file_path] {
+            if file_path.exists() {
+                let _ = fs::remove_file(file_path);
+            }
+        }
+    }
+}
+
+/// Returns the paths of the save game of the user and of its replay
+fn get_file_paths(user: &str) -> (PathBuf, PathBuf) {
+    let file_name: String = user.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect();
+    let directory = Path::new(SAVE_GAME_DIRECTORY);
+
+    (directory.join(format!("{}.xml", file_name)), directory.join(format!("{}.replay", file_name)))
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("could not parse '{}'", value))
+}
+
+fn parse_position(x: &str, y: &str) -> Result<(u16, u16), String> {
+    let (x_pos, y_pos) = (parse(x)?, parse(y)?);
+    if x_pos >= FIELD_WIDTH || y_pos >= FIELD_HEIGHT {
+        return Err(format!("{},{} is outside of the field", x_pos, y_pos));
+    }
+
+    Ok((x_pos, y_pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autopilot::AutopilotController;
+    use crate::controller::{Controller, Observation};
+    use crate::game_mode::GameMode;
+    use crate::game_state::PLAYER_INDEX;
+    use crate::mutator::Mutator;
+    use crate::ruleset::Ruleset;
+
+    #[test]
+    fn growing_snake_is_restored() {
+        let ruleset = Ruleset::built_in().into_iter().find(|ruleset| ruleset.id == "NORMAL").expect("NORMAL is built in");
+        let mut game_state = GameState::new(GameMode::CLASSIC, vec![Mutator::DoubleGrowth]);
+        game_state.reseed(5);
+        let mut replay = Replay::start(&game_state, &ruleset);
+        let mut controller = AutopilotController;
+        // play until the first fruit has been eaten, the growth has not entered the field yet
+        while game_state.current_score == 0 {
+            game_state.player_mut().direction = controller.next_direction(&Observation::new(&game_state, &ruleset, PLAYER_INDEX));
+            replay.record(&game_state);
+            game_state.handle_snake_movement(&ruleset);
+        }
+        assert!(game_state.player().tail_x_pos.iter().any(|x_pos| *x_pos >= FIELD_WIDTH));
+
+        let bytes = SaveGame::to_xml("growing", &game_state, 1234, false, &Splits::default());
+        let save_game = SaveGame::from_xml(replay, &bytes).expect("save game of a growing snake is valid");
+        let (saved, restored) = (game_state.player(), save_game.game_state.player());
+        assert_eq!((restored.x_pos, restored.y_pos), (saved.x_pos, saved.y_pos));
+        assert_eq!(restored.tail_x_pos, saved.tail_x_pos);
+        assert_eq!(restored.tail_y_pos, saved.tail_y_pos);
+        assert_eq!(save_game.game_state.current_score, game_state.current_score);
+        assert_eq!(save_game.game_state.rng.state, game_state.rng.state);
+        assert_eq!(save_game.user, "growing");
+        assert_eq!(save_game.time, 1234);
+    }
+}
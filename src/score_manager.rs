@@ -1,13 +1,36 @@
 use std::fs::{File, OpenOptions};
 use std::fs;
-use std::io::{BufReader, Cursor, Read, Write};
+use std::io::{BufRead, BufReader, Cursor, Read, Write};
 use std::path::Path;
 
 use quick_xml::{Reader, Writer};
 use quick_xml::events::{BytesEnd, BytesStart, Event};
 use quick_xml::events::attributes::Attribute;
 
-use crate::Difficulty;
+use crate::mutator::Mutator;
+use crate::splits::Splits;
+
+/// A recorded score, the time is optional for backwards compatibility
+pub struct Score {
+    pub(crate) score: u64,
+    pub(crate) user: String,
+    pub(crate) time: Option<u64>,
+    pub(crate) mutators: Vec<Mutator>,
+    /// whether hints were shown during the game
+    pub(crate) hints: bool,
+    /// whether the game was resumed from a save game
+    pub(crate) resumed: bool,
+    /// whether the game was ended by a signal before it was over, such scores are never verified
+    pub(crate) aborted: bool,
+    pub(crate) tail_length: Option<u64>,
+    /// times the tail reached the split lengths, empty for scores recorded before splits existed
+    pub(crate) splits: Splits,
+    /// path of the replay of the game
+    pub(crate) replay: Option<String>,
+    /// whether re-simulating the replay led to the recorded result, only verified scores are shown
+    /// on the leaderboards
+    pub(crate) verified: bool,
+}
 
 pub struct ScoreManager<'a> {
     file_path: &'a str
@@ -20,20 +43,20 @@ impl<'a> ScoreManager<'a> {
             File::create(target_path).expect("could not create scores file");
             fs::copy("scores-template.xml", file_path).expect("failed to create scores file from template");
         }
-        return ScoreManager { file_path };
+        ScoreManager { file_path }
     }
 
-    /// Returns the highest n (defined by the limit param) scores in descending order for the
-    /// selected difficulty as a vector of tuples with the score + user name
-    pub fn get_high_scores(&self, difficulty: &Difficulty, limit: usize) -> Vec<(u64, String, Option<u64>)> {
-        // tuple of score, name and time; time is optional for backwards compatibility
-        let mut relevant_scores: Vec<(u64, String, Option<u64>)> = self.get_scores(Some(difficulty));
+    /// Returns the highest n (defined by the limit param) verified scores in descending order for
+    /// the selected leaderboard
+    pub fn get_high_scores(&self, leaderboard_id: &str, limit: usize) -> Vec<Score> {
+        let mut relevant_scores: Vec<Score> = self.get_scores(Some(leaderboard_id));
+        relevant_scores.retain(|score| score.verified);
 
-        relevant_scores.sort_by_key(|tuple| tuple.0);
+        relevant_scores.sort_by_key(|score| score.score);
         relevant_scores.reverse();
         relevant_scores.truncate(limit);
 
-        return relevant_scores;
+        relevant_scores
     }
 
     pub fn get_total_playtime_display(&self) -> String {
@@ -50,75 +73,112 @@ impl<'a> ScoreManager<'a> {
     /// Returns the total playtime across all game modes in millis
     pub fn get_total_playtime(&self) -> u64 {
         let scores = self.get_scores(None);
-        scores.iter().map(|tuple| tuple.2.unwrap_or(0)).sum()
+        scores.iter().map(|score| score.time.unwrap_or(0)).sum()
     }
 
-    /// Returns all scores, optionally only of the specified difficulty
-    pub fn get_scores(&self, difficulty_opt: Option<&Difficulty>) -> Vec<(u64, String, Option<u64>)> {
+    /// Returns all scores, optionally only of the specified leaderboard
+    ///
+    /// Scores are grouped by difficulty elements, the name of which is the id of the leaderboard (see
+    /// GameMode::get_leaderboard_id). Classic games use the id of the ruleset and the built-in rulesets
+    /// use the names of the former difficulties as id so older files stay valid.
+    pub fn get_scores(&self, leaderboard_id_opt: Option<&str>) -> Vec<Score> {
         let mut xml_reader = self.create_reader();
         let mut buf = Vec::new();
-        let mut is_reading_relevant_difficulty = match difficulty_opt {
-            Some(_) => false,
-            None => true
-        };
+        let mut is_reading_relevant_difficulty = leaderboard_id_opt.is_none();
 
-        // tuple of score, name and time; time is optional for backwards compatibility
-        let mut relevant_scores: Vec<(u64, String, Option<u64>)> = Vec::new();
+        let mut relevant_scores: Vec<Score> = Vec::new();
 
         loop {
             match xml_reader.read_event(&mut buf) {
                 // b"" returns the string as u8 byte array
                 Ok(Event::Start(ref elem)) => {
-                    if elem.name() == b"difficulty" && difficulty_opt.is_some() {
-                        let difficulty = difficulty_opt.unwrap();
+                    if let (b"difficulty", Some(leaderboard_id)) = (elem.name(), leaderboard_id_opt) {
                         let found_name_attr = Self::get_name_atr(elem);
 
-                        match found_name_attr {
-                            Some(res) => {
-                                if res.unescape_and_decode_value(&xml_reader).expect("failed to decode name attribute of difficulty element") == difficulty.to_string() {
-                                    is_reading_relevant_difficulty = true;
-                                } else {
-                                    is_reading_relevant_difficulty = false;
-                                }
-                            }
-                            None => {}
+                        if let Some(res) = found_name_attr {
+                            is_reading_relevant_difficulty = res.unescape_and_decode_value(&xml_reader).expect("failed to decode name attribute of difficulty element") == leaderboard_id;
                         }
                     }
                 }
-                Ok(Event::Empty(ref elem)) => {
-                    if elem.name() == b"score" && is_reading_relevant_difficulty {
-                        let mut set_score: Option<u64> = None;
-                        let mut set_name: Option<String> = None;
-                        let mut set_time: Option<u64> = None;
-
-                        for attr in elem.attributes() {
-                            let attribute: Attribute = attr.unwrap();
-
-                            if attribute.key == b"score" {
-                                set_score = Some(attribute.unescape_and_decode_value(&xml_reader).expect("could not decode attribute").parse().expect("could not parse value of attribute score as u64"));
-                            } else if attribute.key == b"user" {
-                                set_name = Some(attribute.unescape_and_decode_value(&xml_reader).expect("could not decode attribute"));
-                            } else if attribute.key == b"time" {
-                                set_time = Some(attribute.unescape_and_decode_value(&xml_reader).expect("could not decode attribute").parse().expect("could not parse value of attribute time as u64"));
-                            }
-                        }
+                Ok(Event::Empty(ref elem)) if elem.name() == b"score" && is_reading_relevant_difficulty => {
+                    if let Some(score) = Self::read_score_elem(elem, &xml_reader) {
+                        relevant_scores.push(score);
+                    }
+                }
+                Ok(Event::Eof) => break,
+                _ => {}
+            }
+        }
 
-                        if let Some(score) = set_score {
-                            if let Some(name) = set_name {
-                                relevant_scores.push((score, name, set_time));
-                            }
+        relevant_scores
+    }
+
+    /// Returns all scores in the order of the file together with the id of their leaderboard
+    pub fn get_scores_by_leaderboard(&self) -> Vec<(String, Score)> {
+        let mut xml_reader = self.create_reader();
+        let mut buf = Vec::new();
+        let mut leaderboard_id = String::new();
+        let mut scores = Vec::new();
+
+        loop {
+            match xml_reader.read_event(&mut buf) {
+                Ok(Event::Start(ref elem)) if elem.name() == b"difficulty" => {
+                    if let Some(attr) = Self::get_name_atr(elem) {
+                        leaderboard_id = attr.unescape_and_decode_value(&xml_reader).expect("failed to decode name attribute of difficulty element");
+                    }
+                }
+                Ok(Event::End(ref elem)) if elem.name() == b"difficulty" => leaderboard_id.clear(),
+                Ok(Event::Empty(ref elem)) if elem.name() == b"score" => {
+                    if let Some(score) = Self::read_score_elem(elem, &xml_reader) {
+                        scores.push((leaderboard_id.clone(), score));
+                    }
+                }
+                Ok(Event::Eof) => break,
+                _ => {}
+            }
+        }
+
+        scores
+    }
+
+    /// Sets the verified flag of all scores in the order of the file, as returned by
+    /// get_scores_by_leaderboard
+    pub fn write_verified_flags(&self, flags: &[bool]) {
+        let mut xml_reader = self.create_reader();
+        let mut buf = Vec::new();
+        let mut writer = Writer::new(Cursor::new(Vec::new()));
+        let mut flags = flags.iter();
+
+        loop {
+            match xml_reader.read_event(&mut buf) {
+                Ok(Event::Empty(elem)) if elem.name() == b"score" && Self::read_score_elem(&elem, &xml_reader).is_some() => {
+                    let verified = *flags.next().expect("a flag for every score");
+                    let mut score_elem = BytesStart::owned(b"score".to_vec(), "score".len());
+                    for attr in elem.attributes() {
+                        let attribute: Attribute = attr.expect("could not read attribute");
+                        if attribute.key != b"verified" {
+                            score_elem.push_attribute(attribute);
                         }
                     }
+                    if verified {
+                        score_elem.push_attribute(("verified", "true"));
+                    }
+                    writer.write_event(Event::Empty(score_elem)).expect("failed to write elem");
                 }
                 Ok(Event::Eof) => break,
+                Ok(e) => {
+                    writer.write_event(&e).expect("failed to write elem");
+                }
                 _ => {}
             }
         }
 
-        return relevant_scores;
+        let mut file = File::create(self.file_path).expect("could not open score file");
+        let bytes = writer.into_inner().into_inner();
+        file.write_all(bytes.as_slice()).expect("failed writing score to file");
     }
 
-    pub fn write_score(&self, score: u64, difficulty: &Difficulty, user_name: &str, time: u128) {
+    pub fn write_score(&self, leaderboard_id: &str, score: &Score) {
         let mut xml_reader = self.create_reader();
         let mut buf = Vec::new();
         let mut writer = Writer::new(Cursor::new(Vec::new()));
@@ -131,12 +191,12 @@ impl<'a> ScoreManager<'a> {
 
                         match name_atr {
                             Some(atr) => {
-                                let is_current_difficulty = atr.unescape_and_decode_value(&xml_reader).expect("failed to decode name attribute of difficulty element") == difficulty.to_string();
+                                let is_current_difficulty = atr.unescape_and_decode_value(&xml_reader).expect("failed to decode name attribute of difficulty element") == leaderboard_id;
                                 // need to borrow before move
                                 writer.write_event(Event::Start(elem)).expect("failed to write elem");
                                 if is_current_difficulty {
                                     difficulty_elem_exists = true;
-                                    let score_elem = Self::create_score_elem(score, user_name, time);
+                                    let score_elem = Self::create_score_elem(score);
 
                                     writer.write_event(Event::Empty(score_elem)).expect("failed to write elem");
                                 }
@@ -153,8 +213,8 @@ impl<'a> ScoreManager<'a> {
                     if elem.name() == b"scores" && !difficulty_elem_exists {
                         let mut difficulty_elem = BytesStart::owned(b"difficulty".to_vec(), "difficulty".len());
 
-                        difficulty_elem.push_attribute(("name", difficulty.to_string().as_str()));
-                        let score_elem = Self::create_score_elem(score, user_name, time);
+                        difficulty_elem.push_attribute(("name", leaderboard_id));
+                        let score_elem = Self::create_score_elem(score);
 
                         writer.write_event(Event::Start(difficulty_elem)).expect("failed to write elem");
                         writer.write_event(Event::Empty(score_elem)).expect("failed to write elem");
@@ -178,7 +238,7 @@ impl<'a> ScoreManager<'a> {
     }
 
     fn create_reader(&self) -> Reader<BufReader<File>> {
-        let score_file = OpenOptions::new().read(true).write(true).create(true).open(self.file_path).expect("Failed to open score file");
+        let score_file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(self.file_path).expect("Failed to open score file");
         let mut file_reader = BufReader::new(&score_file);
         let mut xml_content: String = String::from("");
         file_reader.read_to_string(&mut xml_content).expect("Unable to read xml file");
@@ -186,21 +246,96 @@ impl<'a> ScoreManager<'a> {
         Reader::from_file(Path::new(self.file_path)).expect("failed to initialize xml reader")
     }
 
+    /// Returns the score described by the attributes of a score element, None if the score or the
+    /// user is missing
+    fn read_score_elem<B: BufRead>(elem: &BytesStart, xml_reader: &Reader<B>) -> Option<Score> {
+        let mut set_score: Option<u64> = None;
+        let mut set_name: Option<String> = None;
+        let mut set_time: Option<u64> = None;
+        let mut mutators: Vec<Mutator> = Vec::new();
+        let mut hints = false;
+        let mut resumed = false;
+        let mut aborted = false;
+        let mut tail_length: Option<u64> = None;
+        let mut splits = Splits::default();
+        let mut replay: Option<String> = None;
+        let mut verified = false;
+
+        for attr in elem.attributes() {
+            let attribute: Attribute = attr.unwrap();
+
+            if attribute.key == b"score" {
+                set_score = Some(attribute.unescape_and_decode_value(xml_reader).expect("could not decode attribute").parse().expect("could not parse value of attribute score as u64"));
+            } else if attribute.key == b"user" {
+                set_name = Some(attribute.unescape_and_decode_value(xml_reader).expect("could not decode attribute"));
+            } else if attribute.key == b"time" {
+                set_time = Some(attribute.unescape_and_decode_value(xml_reader).expect("could not decode attribute").parse().expect("could not parse value of attribute time as u64"));
+            } else if attribute.key == b"mutators" {
+                let value = attribute.unescape_and_decode_value(xml_reader).expect("could not decode attribute");
+                // ignore unknown mutator ids so that removing a mutator does not break older score files
+                mutators = value.split(',').filter_map(Mutator::from_id).collect();
+            } else if attribute.key == b"hints" {
+                hints = attribute.unescape_and_decode_value(xml_reader).expect("could not decode attribute").parse().expect("could not parse value of attribute hints as bool");
+            } else if attribute.key == b"resumed" {
+                resumed = attribute.unescape_and_decode_value(xml_reader).expect("could not decode attribute").parse().expect("could not parse value of attribute resumed as bool");
+            } else if attribute.key == b"aborted" {
+                aborted = attribute.unescape_and_decode_value(xml_reader).expect("could not decode attribute").parse().expect("could not parse value of attribute aborted as bool");
+            } else if attribute.key == b"tail-length" {
+                tail_length = Some(attribute.unescape_and_decode_value(xml_reader).expect("could not decode attribute").parse().expect("could not parse value of attribute tail-length as u64"));
+            } else if attribute.key == b"splits" {
+                splits = Splits::parse(&attribute.unescape_and_decode_value(xml_reader).expect("could not decode attribute")).expect("could not parse value of attribute splits");
+            } else if attribute.key == b"replay" {
+                replay = Some(attribute.unescape_and_decode_value(xml_reader).expect("could not decode attribute"));
+            } else if attribute.key == b"verified" {
+                verified = attribute.unescape_and_decode_value(xml_reader).expect("could not decode attribute").parse().expect("could not parse value of attribute verified as bool");
+            }
+        }
+
+        Some(Score { score: set_score?, user: set_name?, time: set_time, mutators, hints, resumed, aborted, tail_length, splits, replay, verified })
+    }
+
     fn get_name_atr<'b>(elem: &'b BytesStart) -> Option<Attribute<'b>> {
-        return elem.attributes()
+        elem.attributes()
             .map(|attr| attr.unwrap())
             .find(|attr| {
                 attr.key == b"name"
-            });
+            })
     }
 
-    fn create_score_elem(score: u64, user_name: &str, time: u128) -> BytesStart {
+    fn create_score_elem(score: &Score) -> BytesStart<'_> {
         let mut score_elem = BytesStart::owned(b"score".to_vec(), "score".len());
 
-        score_elem.push_attribute(("score", score.to_string().as_str()));
-        score_elem.push_attribute(("user", user_name));
-        score_elem.push_attribute(("time", time.to_string().as_str()));
+        score_elem.push_attribute(("score", score.score.to_string().as_str()));
+        score_elem.push_attribute(("user", score.user.as_str()));
+        if let Some(time) = score.time {
+            score_elem.push_attribute(("time", time.to_string().as_str()));
+        }
+        if !score.mutators.is_empty() {
+            let mutator_ids: Vec<&str> = score.mutators.iter().map(|mutator| mutator.get_id()).collect();
+            score_elem.push_attribute(("mutators", mutator_ids.join(",").as_str()));
+        }
+        if score.hints {
+            score_elem.push_attribute(("hints", "true"));
+        }
+        if score.resumed {
+            score_elem.push_attribute(("resumed", "true"));
+        }
+        if score.aborted {
+            score_elem.push_attribute(("aborted", "true"));
+        }
+        if let Some(tail_length) = score.tail_length {
+            score_elem.push_attribute(("tail-length", tail_length.to_string().as_str()));
+        }
+        if !score.splits.times.is_empty() {
+            score_elem.push_attribute(("splits", score.splits.to_string().as_str()));
+        }
+        if let Some(replay) = &score.replay {
+            score_elem.push_attribute(("replay", replay.as_str()));
+        }
+        if score.verified {
+            score_elem.push_attribute(("verified", "true"));
+        }
 
-        return score_elem;
+        score_elem
     }
 }
\ No newline at end of file
@@ -0,0 +1,270 @@
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::decompress_to_vec_with_limit;
+
+use crate::game_mode::GameMode;
+use crate::game_state::GameState;
+use crate::mutator::Mutator;
+use crate::replay::{find_puzzle_level, push_string, ByteReader, Replay, REPLAY_VERSION};
+use crate::ruleset::Ruleset;
+use crate::tron::TronOpponent;
+
+/// First byte of codes holding a whole replay
+const REPLAY_KIND: u8 = b'R';
+/// First byte of codes holding only the setup of a game
+const CHALLENGE_KIND: u8 = b'C';
+/// Number of bytes of the CRC-32 at the end of every code
+const CHECKSUM_LENGTH: usize = 4;
+/// Largest replay a code may expand to, the number of ticks it holds is limited when it is read
+const MAX_REPLAY_SIZE: usize = 1 << 20;
+/// Characters of the URL safe variant of base64, they survive being pasted into chats and links
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// A game to play from the same start as someone else: the seed, the mode and the ruleset, but
+/// none of the moves
+pub struct Challenge {
+    pub(crate) seed: u64,
+    pub(crate) mode: GameMode,
+    pub(crate) tron_opponent: TronOpponent,
+    /// id of the level in puzzle mode
+    pub(crate) puzzle_level: Option<String>,
+    /// the ruleset is looked up by id, challenges are meant to be played on the official leaderboards
+    pub(crate) ruleset_id: String,
+    pub(crate) mutators: Vec<Mutator>,
+}
+
+impl Challenge {
+    /// Returns the challenge to play the same game as the replay
+    pub fn from_replay(replay: &Replay) -> Self {
+        Challenge {
+            seed: replay.seed,
+            mode: replay.mode,
+            tron_opponent: replay.tron_opponent,
+            puzzle_level: replay.puzzle_level.clone(),
+            ruleset_id: replay.ruleset.id.clone(),
+            mutators: replay.mutators.clone(),
+        }
+    }
+
+    /// Returns the ruleset of the challenge among the provided ones, puzzle levels bring their own
+    pub fn find_ruleset(&self, rulesets: &[Ruleset]) -> Result<Ruleset, String> {
+        if let Some(level_id) = &self.puzzle_level {
+            return Ok(find_puzzle_level(level_id).expect("puzzle level checked when decoding").to_ruleset());
+        }
+
+        rulesets.iter()
+            .find(|ruleset| ruleset.id == self.ruleset_id)
+            .cloned()
+            .ok_or(format!("the challenge is played with the ruleset '{}', which is not defined here", self.ruleset_id))
+    }
+
+    /// Returns the game of the challenge before the first tick
+    pub fn create_game_state(&self) -> GameState {
+        let mut game_state = match (self.mode, &self.puzzle_level) {
+            (GameMode::PUZZLE, Some(level_id)) => {
+                GameState::new_puzzle(find_puzzle_level(level_id).expect("puzzle level checked when decoding").clone())
+            }
+            (GameMode::TRON, _) => GameState::new_tron(self.tron_opponent, self.mutators.clone()),
+            (mode, _) => GameState::new(mode, self.mutators.clone()),
+        };
+        game_state.reseed(self.seed);
+
+        game_state
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![REPLAY_VERSION];
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        push_string(&mut bytes, &self.mode.to_string());
+        push_string(&mut bytes, &self.tron_opponent.to_string());
+        push_string(&mut bytes, self.puzzle_level.as_deref().unwrap_or(""));
+        push_string(&mut bytes, &self.ruleset_id);
+        bytes.push(self.mutators.len() as u8);
+        for mutator in self.mutators.iter() {
+            push_string(&mut bytes, mutator.get_id());
+        }
+
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Challenge, String> {
+        let mut reader = ByteReader::new(bytes);
+        let version = reader.read_u8()?;
+        // fruits only show up in the same places with the same game logic
+        if version != REPLAY_VERSION {
+            return Err(format!("created with version {} of the game logic, this build plays version {}", version, REPLAY_VERSION));
+        }
+        let mut seed_bytes = [0; 8];
+        seed_bytes.copy_from_slice(reader.read_bytes(8)?);
+        let seed = u64::from_le_bytes(seed_bytes);
+
+        let mode_name = reader.read_string()?;
+        let mode = GameMode::all().iter().copied().find(|mode| mode.to_string() == mode_name)
+            .ok_or(format!("unknown mode '{}'", mode_name))?;
+        let opponent_name = reader.read_string()?;
+        let tron_opponent = TronOpponent::all().iter().copied().find(|opponent| opponent.to_string() == opponent_name)
+            .ok_or(format!("unknown opponent '{}'", opponent_name))?;
+        let puzzle_level = Some(reader.read_string()?).filter(|level_id| !level_id.is_empty());
+        match &puzzle_level {
+            Some(level_id) if find_puzzle_level(level_id).is_none() => return Err(format!("unknown puzzle level '{}'", level_id)),
+            None if mode == GameMode::PUZZLE => return Err(String::from("puzzle level is missing")),
+            _ => {}
+        }
+        let ruleset_id = reader.read_string()?;
+
+        let mutator_count = reader.read_u8()?;
+        let mut mutators = Vec::new();
+        for _ in 0..mutator_count {
+            let mutator_id = reader.read_string()?;
+            mutators.push(Mutator::from_id(&mutator_id).ok_or(format!("unknown mutator '{}'", mutator_id))?);
+        }
+        Mutator::check_mode(&mutators, mode)?;
+        if reader.position != bytes.len() {
+            return Err(String::from("unexpected data after the mutators"));
+        }
+
+        Ok(Challenge { seed, mode, tron_opponent, puzzle_level, ruleset_id, mutators })
+    }
+}
+
+/// A replay or a challenge as short text to copy and paste. The bytes are followed by a checksum
+/// and written in base64, replays are compressed first.
+pub enum ShareCode {
+    Replay(Replay),
+    Challenge(Challenge),
+}
+
+impl ShareCode {
+    pub fn encode(&self) -> String {
+        let mut bytes = match self {
+            ShareCode::Replay(replay) => {
+                let mut bytes = vec![REPLAY_KIND];
+                bytes.extend(compress_to_vec(&replay.to_bytes(), 10));
+                bytes
+            }
+            ShareCode::Challenge(challenge) => {
+                let mut bytes = vec![CHALLENGE_KIND];
+                bytes.extend(challenge.to_bytes());
+                bytes
+            }
+        };
+        bytes.extend_from_slice(&crc32(&bytes).to_le_bytes());
+
+        encode_base64(&bytes)
+    }
+
+    /// Decodes the code, whitespace is ignored since long codes are often wrapped when pasted
+    pub fn decode(code: &str) -> Result<ShareCode, String> {
+        let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+        if code.is_empty() {
+            return Err(String::from("share code is empty"));
+        }
+        let bytes = decode_base64(&code)?;
+        if bytes.len() <= CHECKSUM_LENGTH {
+            return Err(String::from("share code is truncated"));
+        }
+
+        let (data, checksum) = bytes.split_at(bytes.len() - CHECKSUM_LENGTH);
+        if crc32(data).to_le_bytes() != checksum {
+            return Err(String::from("share code is damaged, it is incomplete or has a typo"));
+        }
+        match data[0] {
+            REPLAY_KIND => {
+                let replay_bytes = decompress_to_vec_with_limit(&data[1..], MAX_REPLAY_SIZE)
+                    .map_err(|_| String::from("share code holds no valid replay"))?;
+                let replay = Replay::from_bytes(&replay_bytes).map_err(|error| format!("share code holds an invalid replay: {}", error))?;
+                Ok(ShareCode::Replay(replay))
+            }
+            CHALLENGE_KIND => {
+                let challenge = Challenge::from_bytes(&data[1..]).map_err(|error| format!("share code holds an invalid challenge: {}", error))?;
+                Ok(ShareCode::Challenge(challenge))
+            }
+            kind => Err(format!("unknown kind of share code '{}'", kind as char)),
+        }
+    }
+
+    /// Returns the challenge of the code, replay codes challenge to play the same game
+    pub fn into_challenge(self) -> Challenge {
+        match self {
+            ShareCode::Replay(replay) => Challenge::from_replay(&replay),
+            ShareCode::Challenge(challenge) => challenge,
+        }
+    }
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut code = String::new();
+    for chunk in bytes.chunks(3) {
+        let value = chunk.iter().enumerate().fold(0u32, |value, (i, byte)| value | (*byte as u32) << (16 - 8 * i));
+        // n bytes take n + 1 characters, no padding is added
+        for i in 0..=chunk.len() {
+            code.push(ALPHABET[(value >> (18 - 6 * i) & 0x3F) as usize] as char);
+        }
+    }
+
+    code
+}
+
+fn decode_base64(code: &str) -> Result<Vec<u8>, String> {
+    let mut values = Vec::new();
+    for c in code.chars() {
+        let value = ALPHABET.iter().position(|other| *other as char == c)
+            .ok_or(format!("'{}' is not part of a share code", c))?;
+        values.push(value as u32);
+    }
+
+    let mut bytes = Vec::new();
+    for chunk in values.chunks(4) {
+        // a single character is not a whole byte, the code was cut off
+        if chunk.len() == 1 {
+            return Err(String::from("share code is truncated"));
+        }
+        let value = chunk.iter().enumerate().fold(0u32, |value, (i, sextet)| value | sextet << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            bytes.push((value >> (16 - 8 * i)) as u8);
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Returns the CRC-32 of the bytes as used by zip and PNG
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { crc >> 1 ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_replay_with_too_many_ticks() {
+        let mut game_state = GameState::new(GameMode::CLASSIC, Vec::new());
+        game_state.reseed(1);
+        let mut replay = Replay::start(&game_state, &Ruleset::built_in()[0]);
+        game_state.player_mut().direction = crate::direction::Direction::RIGHT;
+        replay.record(&game_state);
+
+        // swap the single run for one claiming far more ticks than a replay may hold
+        let mut replay_bytes = replay.to_bytes();
+        // the replay ends with the tick count, the direction code and the length of the run
+        let code = replay_bytes[replay_bytes.len() - 2];
+        replay_bytes.truncate(replay_bytes.len() - 3);
+        crate::replay::push_varint(&mut replay_bytes, 1 << 40);
+        replay_bytes.push(code);
+        crate::replay::push_varint(&mut replay_bytes, 1 << 40);
+
+        let mut bytes = vec![REPLAY_KIND];
+        bytes.extend(compress_to_vec(&replay_bytes, 10));
+        bytes.extend_from_slice(&crc32(&bytes).to_le_bytes());
+        let error = ShareCode::decode(&encode_base64(&bytes)).err().expect("crafted code is rejected");
+        assert!(error.contains("ticks"), "{}", error);
+    }
+}
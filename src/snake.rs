@@ -1,24 +1,48 @@
 use crate::{FIELD_HEIGHT, FIELD_WIDTH};
+use crate::death_cause::DeathCause;
 use crate::direction::Direction;
 
+#[derive(Clone)]
 pub struct Snake {
     pub(crate) x_pos: u16,
     pub(crate) y_pos: u16,
     pub(crate) tail_x_pos: Vec<u16>,
     pub(crate) tail_y_pos: Vec<u16>,
+    pub(crate) direction: Direction,
+    pub(crate) alive: bool,
+    pub(crate) death_cause: Option<DeathCause>,
+    /// whether the direction of the snake is chosen by the built-in AI instead of a player
+    pub(crate) ai_controlled: bool,
 }
 
 impl Snake {
     pub fn new() -> Snake {
+        // spawn head in the middle of the field
+        Self::spawn_at(FIELD_WIDTH / 2, FIELD_HEIGHT / 2)
+    }
+
+    pub fn spawn_at(x_pos: u16, y_pos: u16) -> Snake {
         Snake {
-            // spawn head in the middle of the field
-            x_pos: FIELD_WIDTH / 2,
-            y_pos: FIELD_HEIGHT / 2,
+            x_pos,
+            y_pos,
             tail_x_pos: Vec::new(),
             tail_y_pos: Vec::new(),
+            direction: Direction::STOP,
+            alive: true,
+            death_cause: None,
+            ai_controlled: false,
         }
     }
 
+    pub fn kill(&mut self, death_cause: DeathCause) {
+        self.alive = false;
+        self.death_cause = Some(death_cause);
+    }
+
+    pub fn tail_contains(&self, x_pos: u16, y_pos: u16) -> bool {
+        self.tail_x_pos.iter().zip(self.tail_y_pos.iter()).any(|(x, y)| *x == x_pos && *y == y_pos)
+    }
+
     pub fn move_pos(&mut self, direction: &Direction) {
         match direction {
             Direction::UP => {
@@ -42,7 +66,7 @@ impl Snake {
     }
 
     pub fn move_tail(&mut self) {
-        if self.tail_x_pos.len() == 0 {
+        if self.tail_x_pos.is_empty() {
             return;
         }
 
@@ -88,13 +112,6 @@ impl Snake {
             }
         }
 
-        return matrix;
-    }
-
-    pub fn reset(&mut self) {
-        self.x_pos = FIELD_WIDTH / 2;
-        self.y_pos = FIELD_HEIGHT / 2;
-        self.tail_x_pos.clear();
-        self.tail_y_pos.clear();
+        matrix
     }
 }
\ No newline at end of file
@@ -0,0 +1,326 @@
+use std::fmt;
+use std::time::Instant;
+
+use crate::{FIELD_HEIGHT, FIELD_WIDTH};
+use crate::direction::Direction;
+use crate::game_mode::GameMode;
+use crate::game_state::GameState;
+use crate::rng::Rng;
+use crate::ruleset::Ruleset;
+
+/// Offsets of the eight rays cast from the head, clockwise starting upwards
+const RAY_DIRECTIONS: [(i32, i32); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+/// The actions in the order of their codes
+pub const ACTIONS: [Direction; 4] = [Direction::UP, Direction::DOWN, Direction::LEFT, Direction::RIGHT];
+
+/// How the state of the game is encoded for the agent, all values are between 0 and 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObservationKind {
+    /// one plane per kind of object (head, tail, fruit, wall) covering the whole field, row by row
+    Grid,
+    /// planes of obstacles (walls and snakes) and fruits in a square window centered on the head,
+    /// fields outside of the field count as obstacles
+    Egocentric { radius: u16 },
+    /// for each of eight rays cast from the head the inverse distance to the wall, the tail and a
+    /// fruit (0 if the ray hits none), followed by the heading as one-hot vector
+    Raycast,
+}
+
+impl fmt::Display for ObservationKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObservationKind::Grid => write!(f, "grid"),
+            ObservationKind::Egocentric { radius } => write!(f, "egocentric (radius {})", radius),
+            ObservationKind::Raycast => write!(f, "raycast"),
+        }
+    }
+}
+
+impl ObservationKind {
+    /// Returns the number of values of an observation
+    pub fn size(&self) -> usize {
+        match self {
+            ObservationKind::Grid => 4 * FIELD_WIDTH as usize * FIELD_HEIGHT as usize,
+            ObservationKind::Egocentric { radius } => 2 * (2 * *radius as usize + 1).pow(2),
+            ObservationKind::Raycast => 3 * RAY_DIRECTIONS.len() + ACTIONS.len(),
+        }
+    }
+
+    /// Returns true if every value of an observation is either 0 or 1
+    pub fn is_binary(&self) -> bool {
+        *self != ObservationKind::Raycast
+    }
+
+    /// Writes the observation of the player's snake to the buffer, which holds size() values. The
+    /// wall plane of the grid is only written if requested, otherwise it is left as it is.
+    pub fn encode(&self, game_state: &GameState, observation: &mut [f32], include_wall_plane: bool) {
+        let cleared_values = match self {
+            ObservationKind::Grid if !include_wall_plane => 3 * FIELD_WIDTH as usize * FIELD_HEIGHT as usize,
+            _ => observation.len(),
+        };
+        for value in observation[..cleared_values].iter_mut() {
+            *value = 0.0;
+        }
+
+        match self {
+            ObservationKind::Grid => {
+                encode_grid(game_state, observation);
+                if include_wall_plane {
+                    encode_wall_plane(game_state, observation);
+                }
+            }
+            ObservationKind::Egocentric { radius } => encode_egocentric(game_state, observation, *radius),
+            ObservationKind::Raycast => encode_raycast(game_state, observation),
+        }
+    }
+}
+
+/// Rewards handed out per step, all of them add up
+#[derive(Debug, Clone)]
+pub struct RewardConfig {
+    pub(crate) fruit: f32,
+    pub(crate) death: f32,
+    /// the board is full
+    pub(crate) win: f32,
+    /// handed out on every step, usually a small penalty to discourage stalling
+    pub(crate) step: f32,
+    /// multiplied by the change of the distance to the closest fruit, positive values reward
+    /// approaching it
+    pub(crate) approach: f32,
+}
+
+impl Default for RewardConfig {
+    fn default() -> Self {
+        RewardConfig {
+            fruit: 1.0,
+            death: -1.0,
+            win: 10.0,
+            step: 0.0,
+            approach: 0.0,
+        }
+    }
+}
+
+/// Environment for reinforcement learning in the style of Gym: a single snake in classic mode, each
+/// step takes an action and returns the observation, the reward and whether the episode is done.
+/// The observation is written to a buffer owned by the environment, so steps do not allocate.
+pub struct SnakeEnv {
+    game_state: GameState,
+    ruleset: Ruleset,
+    observation_kind: ObservationKind,
+    reward_config: RewardConfig,
+    /// episodes end after this many steps
+    max_steps: u64,
+    observation: Vec<f32>,
+}
+
+impl SnakeEnv {
+    pub fn new(ruleset: Ruleset, observation_kind: ObservationKind, reward_config: RewardConfig, max_steps: u64) -> Self {
+        let mut env = SnakeEnv {
+            game_state: GameState::new(GameMode::CLASSIC, Vec::new()),
+            ruleset,
+            observation_kind,
+            reward_config,
+            max_steps,
+            observation: vec![0.0; observation_kind.size()],
+        };
+        if observation_kind == ObservationKind::Grid {
+            encode_wall_plane(&env.game_state, &mut env.observation);
+        }
+
+        env
+    }
+
+    /// Starts a new episode, the seed determines the placement of the fruits
+    pub fn reset(&mut self, seed: u64) -> &[f32] {
+        self.game_state.reseed(seed);
+        self.encode_observation();
+
+        &self.observation
+    }
+
+    /// Moves the snake in the direction of the action, stopping is not an action and keeps the
+    /// current direction
+    pub fn step(&mut self, action: Direction) -> (&[f32], f32, bool) {
+        let tail_length = self.game_state.player().tail_x_pos.len();
+        let fruit_distance = self.get_fruit_distance();
+
+        if action != Direction::STOP {
+            self.game_state.player_mut().direction = action;
+        } else if self.game_state.player().direction == Direction::STOP {
+            // the game only starts once the snake moves
+            self.game_state.player_mut().direction = Direction::UP;
+        }
+        self.game_state.handle_snake_movement(&self.ruleset);
+
+        let player = self.game_state.player();
+        let mut reward = self.reward_config.step;
+        if player.tail_x_pos.len() > tail_length {
+            reward += self.reward_config.fruit;
+        } else if player.alive {
+            reward += self.reward_config.approach * (fruit_distance - self.get_fruit_distance()) as f32;
+        }
+        if !player.alive {
+            reward += self.reward_config.death;
+        }
+        if self.game_state.won {
+            reward += self.reward_config.win;
+        }
+
+        let done = self.game_state.game_over || self.game_state.tick_count >= self.max_steps;
+        self.encode_observation();
+
+        (&self.observation, reward, done)
+    }
+
+    fn get_fruit_distance(&self) -> i32 {
+        let player = self.game_state.player();
+        self.game_state.fruits.iter()
+            .map(|fruit| (fruit.x_pos as i32 - player.x_pos as i32).abs() + (fruit.y_pos as i32 - player.y_pos as i32).abs())
+            .min()
+            .unwrap_or(0)
+    }
+
+    fn encode_observation(&mut self) {
+        // the walls never move in classic mode, so the wall plane of the grid is only encoded once
+        self.observation_kind.encode(&self.game_state, &mut self.observation, false);
+    }
+}
+
+fn encode_grid(game_state: &GameState, observation: &mut [f32]) {
+    let plane_size = FIELD_WIDTH as usize * FIELD_HEIGHT as usize;
+    let index = |plane: usize, x_pos: u16, y_pos: u16| plane * plane_size + y_pos as usize * FIELD_WIDTH as usize + x_pos as usize;
+    let player = game_state.player();
+
+    if player.alive {
+        observation[index(0, player.x_pos, player.y_pos)] = 1.0;
+    }
+    for (x_pos, y_pos) in player.tail_x_pos.iter().zip(player.tail_y_pos.iter()) {
+        // mind that newly created tail elements are spawned out of view
+        if *x_pos < FIELD_WIDTH && *y_pos < FIELD_HEIGHT {
+            observation[index(1, *x_pos, *y_pos)] = 1.0;
+        }
+    }
+    for fruit in game_state.fruits.iter() {
+        observation[index(2, fruit.x_pos, fruit.y_pos)] = 1.0;
+    }
+}
+
+fn encode_wall_plane(game_state: &GameState, observation: &mut [f32]) {
+    let plane_size = FIELD_WIDTH as usize * FIELD_HEIGHT as usize;
+    for y_pos in 0..FIELD_HEIGHT {
+        for x_pos in 0..FIELD_WIDTH {
+            if game_state.is_wall(x_pos, y_pos) {
+                observation[3 * plane_size + y_pos as usize * FIELD_WIDTH as usize + x_pos as usize] = 1.0;
+            }
+        }
+    }
+}
+
+fn encode_egocentric(game_state: &GameState, observation: &mut [f32], radius: u16) {
+    let size = 2 * radius as i32 + 1;
+    let player = game_state.player();
+    let (head_x, head_y) = (player.x_pos as i32, player.y_pos as i32);
+
+    for window_y in 0..size {
+        for window_x in 0..size {
+            let (x_pos, y_pos) = (head_x + window_x - radius as i32, head_y + window_y - radius as i32);
+            let index = (window_y * size + window_x) as usize;
+            let outside = x_pos < 0 || y_pos < 0 || x_pos >= FIELD_WIDTH as i32 || y_pos >= FIELD_HEIGHT as i32;
+            if outside || game_state.is_wall(x_pos as u16, y_pos as u16) || player.tail_contains(x_pos as u16, y_pos as u16) {
+                observation[index] = 1.0;
+            }
+        }
+    }
+    for fruit in game_state.fruits.iter() {
+        let (window_x, window_y) = (fruit.x_pos as i32 - head_x + radius as i32, fruit.y_pos as i32 - head_y + radius as i32);
+        if window_x >= 0 && window_y >= 0 && window_x < size && window_y < size {
+            observation[(size * size + window_y * size + window_x) as usize] = 1.0;
+        }
+    }
+}
+
+fn encode_raycast(game_state: &GameState, observation: &mut [f32]) {
+    let player = game_state.player();
+
+    for (ray, (delta_x, delta_y)) in RAY_DIRECTIONS.iter().enumerate() {
+        let (mut x_pos, mut y_pos) = (player.x_pos as i32, player.y_pos as i32);
+        let mut distance = 0;
+        let (mut tail_distance, mut fruit_distance) = (None, None);
+
+        let wall_distance = loop {
+            x_pos += delta_x;
+            y_pos += delta_y;
+            distance += 1;
+            if game_state.is_wall(x_pos as u16, y_pos as u16) {
+                break distance;
+            }
+            if tail_distance.is_none() && player.tail_contains(x_pos as u16, y_pos as u16) {
+                tail_distance = Some(distance);
+            }
+            if fruit_distance.is_none() && game_state.fruits.iter().any(|fruit| fruit.x_pos as i32 == x_pos && fruit.y_pos as i32 == y_pos) {
+                fruit_distance = Some(distance);
+            }
+        };
+
+        observation[3 * ray] = 1.0 / wall_distance as f32;
+        observation[3 * ray + 1] = tail_distance.map(|distance| 1.0 / distance as f32).unwrap_or(0.0);
+        observation[3 * ray + 2] = fruit_distance.map(|distance| 1.0 / distance as f32).unwrap_or(0.0);
+    }
+
+    if let Some(heading) = ACTIONS.iter().position(|direction| *direction == player.direction) {
+        observation[3 * RAY_DIRECTIONS.len() + heading] = 1.0;
+    }
+}
+
+/// Options of the `bench-env` command
+pub struct EnvBenchOptions {
+    pub(crate) observation_kind: ObservationKind,
+    pub(crate) steps: u64,
+    pub(crate) seed: u64,
+    pub(crate) ruleset_id: String,
+}
+
+impl Default for EnvBenchOptions {
+    fn default() -> Self {
+        EnvBenchOptions {
+            observation_kind: ObservationKind::Raycast,
+            steps: 1_000_000,
+            seed: 0,
+            ruleset_id: String::from("NORMAL"),
+        }
+    }
+}
+
+/// Steps the environment with random actions that never reverse into the tail and prints the number
+/// of steps per second
+pub fn run_benchmark(options: &EnvBenchOptions, ruleset: &Ruleset) {
+    let mut env = SnakeEnv::new(ruleset.clone(), options.observation_kind, RewardConfig::default(), u64::MAX);
+    let mut rng = Rng::new(options.seed);
+    let mut episodes = 1;
+    let mut total_reward = 0.0;
+    env.reset(options.seed);
+
+    let started = Instant::now();
+    for _ in 0..options.steps {
+        let current_direction = env.game_state.player().direction;
+        let action = loop {
+            let action = ACTIONS[rng.gen_range(0, ACTIONS.len() as u64) as usize];
+            if action != current_direction.opposite() {
+                break action;
+            }
+        };
+
+        let (_, reward, done) = env.step(action);
+        total_reward += reward as f64;
+        if done {
+            env.reset(options.seed.wrapping_add(episodes));
+            episodes += 1;
+        }
+    }
+    let seconds = started.elapsed().as_secs_f64();
+
+    println!("{} steps with {} observations of {} values", options.steps, options.observation_kind, options.observation_kind.size());
+    println!("{} episodes, mean reward per episode {:.3}", episodes, total_reward / episodes as f64);
+    println!("{:.0} steps per second", options.steps as f64 / seconds);
+}
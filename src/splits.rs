@@ -0,0 +1,110 @@
+use std::cmp::Reverse;
+use std::fmt;
+use std::time::Duration;
+
+use crate::duration_formatter::DurationFormatter;
+use crate::score_manager::ScoreManager;
+
+/// Tail lengths at which a split is taken
+pub const SPLIT_TAIL_LENGTHS: [usize; 4] = [10, 25, 50, 100];
+
+/// Times at which the tail of the player reached the split tail lengths, like the splits of a
+/// speedrun. Only the time played counts, pauses are excluded.
+#[derive(Clone, Default)]
+pub struct Splits {
+    /// time in millis of every split reached so far, in the order of the tail lengths
+    pub(crate) times: Vec<u64>,
+}
+
+impl Splits {
+    /// Returns the splits of the user's personal best on the leaderboard: the verified run that
+    /// reached the most splits, the fastest one if several reached as many
+    pub fn find_personal_best(score_manager: &ScoreManager, leaderboard_id: &str, user: &str) -> Option<Splits> {
+        score_manager.get_scores(Some(leaderboard_id)).into_iter()
+            .filter(|score| score.verified && score.user == user && !score.splits.times.is_empty())
+            .map(|score| score.splits)
+            .min_by_key(|splits| (Reverse(splits.times.len()), *splits.times.last().expect("splits are not empty")))
+    }
+
+    /// Takes the splits the tail length reached since the last update
+    pub fn update(&mut self, tail_length: usize, elapsed: Duration) {
+        while self.times.len() < SPLIT_TAIL_LENGTHS.len() && tail_length >= SPLIT_TAIL_LENGTHS[self.times.len()] {
+            self.times.push(elapsed.as_millis() as u64);
+        }
+    }
+
+    /// Returns the HUD lines of the last split taken and of the next one, which is compared against
+    /// the personal best while it runs
+    pub fn get_hud_display(&self, personal_best: Option<&Splits>, elapsed: Duration) -> String {
+        let mut display = String::new();
+        if let Some(index) = self.times.len().checked_sub(1) {
+            let time = self.times[index];
+            display.push_str(format!("Split:                                  {} at {}", SPLIT_TAIL_LENGTHS[index], format_time(time)).as_str());
+            if let Some(best_time) = personal_best.and_then(|splits| splits.times.get(index)) {
+                display.push_str(format!(" ({})", format_delta(time as i64 - *best_time as i64)).as_str());
+            }
+            display.push('\n');
+        }
+
+        let index = self.times.len();
+        display.push_str("Next split:                             ");
+        match SPLIT_TAIL_LENGTHS.get(index) {
+            Some(tail_length) => {
+                display.push_str(tail_length.to_string().as_str());
+                if let Some(best_time) = personal_best.and_then(|splits| splits.times.get(index)) {
+                    let delta = elapsed.as_millis() as i64 - *best_time as i64;
+                    display.push_str(format!(", PB {} ({})", format_time(*best_time), format_delta(delta)).as_str());
+                }
+            }
+            None => display.push_str("all splits taken"),
+        }
+
+        display
+    }
+
+    /// Returns a table with the time of every split next to the personal best
+    pub fn render_table(&self, personal_best: Option<&Splits>) -> String {
+        let mut table = format!("{:<8}{:<12}{:<12}{}\n", "Tail", "Time", "PB", "Delta");
+        for (index, tail_length) in SPLIT_TAIL_LENGTHS.iter().enumerate() {
+            let time = self.times.get(index);
+            let best_time = personal_best.and_then(|splits| splits.times.get(index));
+            let delta = match (time, best_time) {
+                (Some(time), Some(best_time)) => format_delta(*time as i64 - *best_time as i64),
+                _ => String::from("-"),
+            };
+            table.push_str(format!(
+                "{:<8}{:<12}{:<12}{}\n",
+                tail_length, time.map_or(String::from("-"), |time| format_time(*time)),
+                best_time.map_or(String::from("-"), |time| format_time(*time)), delta,
+            ).as_str());
+        }
+
+        table
+    }
+
+    /// Parses splits as displayed, the times separated by commas
+    pub fn parse(value: &str) -> Option<Splits> {
+        let times: Option<Vec<u64>> = value.split(',').filter(|time| !time.is_empty()).map(|time| time.parse().ok()).collect();
+        Some(Splits { times: times? })
+    }
+}
+
+impl fmt::Display for Splits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let times: Vec<String> = self.times.iter().map(|time| time.to_string()).collect();
+        write!(f, "{}", times.join(","))
+    }
+}
+
+/// Formats the time with tenths of a second, splits are often close
+fn format_time(millis: u64) -> String {
+    format!("{}.{}", Duration::from_millis(millis).format_duration(), millis % 1000 / 100)
+}
+
+/// Formats the difference to the personal best in seconds, negative when ahead of it
+fn format_delta(delta_millis: i64) -> String {
+    let sign = if delta_millis < 0 { '-' } else { '+' };
+    let millis = delta_millis.unsigned_abs();
+
+    format!("{}{}.{}", sign, millis / 1000, millis % 1000 / 100)
+}
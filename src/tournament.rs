@@ -0,0 +1,311 @@
+use std::fmt;
+use std::time::Instant;
+
+use crate::arena;
+use crate::controller::{self, Controller, Observation};
+use crate::direction::Direction;
+use crate::duration_formatter::DurationFormatter;
+use crate::game_mode::GameMode;
+use crate::game_state::GameState;
+use crate::rating_manager::{Rating, RatingManager};
+use crate::ruleset::Ruleset;
+use crate::tron::TronOpponent;
+
+/// Number of points a rating changes by at most per match
+const K_FACTOR: f64 = 32.0;
+
+/// The kind of matches played in a tournament
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TournamentFormat {
+    /// light cycle duels, the last one moving wins
+    VERSUS,
+    /// up to four snakes in the shrinking arena, ranked by how long they survive
+    ARENA,
+}
+
+impl fmt::Display for TournamentFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl TournamentFormat {
+    /// Returns the number of controllers taking part in each match
+    fn get_match_size(&self) -> usize {
+        match self {
+            TournamentFormat::VERSUS => 2,
+            TournamentFormat::ARENA => 1 + arena::RIVAL_COUNT,
+        }
+    }
+}
+
+/// Options of the `tournament` command
+pub struct TournamentOptions {
+    pub(crate) controllers: Vec<String>,
+    pub(crate) format: TournamentFormat,
+    /// number of seeds every group of controllers plays on
+    pub(crate) seeds: u64,
+    /// first seed, each further seed is the next number
+    pub(crate) seed: u64,
+    pub(crate) ruleset_id: String,
+    /// matches still running after this many ticks are ranked by tail length
+    pub(crate) max_ticks: u64,
+    pub(crate) ratings_file: String,
+}
+
+impl Default for TournamentOptions {
+    fn default() -> Self {
+        TournamentOptions {
+            controllers: Vec::new(),
+            format: TournamentFormat::VERSUS,
+            seeds: 10,
+            seed: 0,
+            ruleset_id: String::from("NORMAL"),
+            max_ticks: 10_000,
+            ratings_file: String::from("ratings.xml"),
+        }
+    }
+}
+
+/// The results of a controller in this tournament
+struct Standing {
+    /// index of the rating of the controller in the pool
+    rating_index: usize,
+    initial_elo: f64,
+    wins: u64,
+    draws: u64,
+    losses: u64,
+    disqualifications: u64,
+}
+
+struct MatchResult {
+    /// number of seats that did strictly better than each seat, so 0 is first place
+    placements: Vec<usize>,
+    disqualifications: Vec<Option<String>>,
+}
+
+/// Returns the id of the pool the ratings of a tournament go into, ratings are only comparable
+/// between matches of the same format and ruleset
+pub fn get_pool_id(format: TournamentFormat, ruleset: &Ruleset) -> String {
+    format!("{}:{}", format, ruleset.id)
+}
+
+/// Plays a round robin between the controllers: every group of them plays on every seed, once from
+/// every seating so that no controller profits from its spawn position. The Elo ratings are updated
+/// after each match and written to the ratings file once all matches are played.
+pub fn run(options: &TournamentOptions, ruleset: &Ruleset) {
+    let started = Instant::now();
+    let pool_id = get_pool_id(options.format, ruleset);
+    let rating_manager = RatingManager::from_file(&options.ratings_file);
+    let mut ratings = rating_manager.get_ratings(&pool_id);
+
+    let mut standings: Vec<Standing> = Vec::new();
+    for name in options.controllers.iter() {
+        let rating_index = match ratings.iter().position(|rating| rating.controller == *name) {
+            Some(index) => index,
+            None => {
+                ratings.push(Rating::new(name));
+                ratings.len() - 1
+            }
+        };
+        standings.push(Standing {
+            rating_index,
+            initial_elo: ratings[rating_index].elo,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            disqualifications: 0,
+        });
+    }
+
+    let match_size = options.format.get_match_size().min(options.controllers.len());
+    let groups = combinations(options.controllers.len(), match_size);
+    let mut match_count = 0;
+    for seed in (0..options.seeds).map(|i| options.seed.wrapping_add(i)) {
+        for group in groups.iter() {
+            for rotation in 0..group.len() {
+                let seating: Vec<usize> = (0..group.len()).map(|seat| group[(seat + rotation) % group.len()]).collect();
+                let names: Vec<&str> = seating.iter().map(|i| options.controllers[*i].as_str()).collect();
+                let result = play_match(options, ruleset, &names, seed);
+                match_count += 1;
+
+                let best_count = result.placements.iter().filter(|placement| **placement == 0).count();
+                for (seat, controller_index) in seating.iter().enumerate() {
+                    let standing = &mut standings[*controller_index];
+                    match (result.placements[seat], best_count) {
+                        (0, 1) => standing.wins += 1,
+                        (0, _) => standing.draws += 1,
+                        _ => standing.losses += 1,
+                    }
+                    if let Some(reason) = &result.disqualifications[seat] {
+                        standing.disqualifications += 1;
+                        println!("{} disqualified in match {} (seed {}): {}", names[seat], match_count, seed, reason);
+                    }
+                }
+
+                let rating_indices: Vec<usize> = seating.iter().map(|i| standings[*i].rating_index).collect();
+                update_ratings(&mut ratings, &rating_indices, &result.placements);
+            }
+        }
+    }
+
+    rating_manager.write_ratings(&pool_id, &ratings);
+    print_standings(options, ruleset, &standings, &ratings, match_count, started);
+}
+
+/// Plays a single match with the controllers seated in the provided order, the first one takes the
+/// place of the player
+fn play_match(options: &TournamentOptions, ruleset: &Ruleset, names: &[&str], seed: u64) -> MatchResult {
+    let mut game_state = match options.format {
+        TournamentFormat::VERSUS => GameState::new_tron(TronOpponent::LOCAL, Vec::new()),
+        TournamentFormat::ARENA => GameState::new(GameMode::ARENA, Vec::new()),
+    };
+    game_state.reseed(seed);
+    game_state.snakes.truncate(names.len());
+    for snake in game_state.snakes.iter_mut() {
+        snake.ai_controlled = false;
+    }
+
+    let mut controllers: Vec<Box<dyn Controller>> = names.iter()
+        .map(|name| controller::create_bot(name).expect("controllers checked when parsing the options"))
+        .collect();
+    let mut disqualifications: Vec<Option<String>> = vec![None; names.len()];
+    let mut eliminated_at: Vec<Option<u64>> = vec![None; names.len()];
+
+    // counting the iterations instead of the ticks makes sure the match ends even if it stalls
+    for _ in 0..options.max_ticks {
+        if game_state.snakes.iter().filter(|snake| snake.alive).count() <= 1 {
+            break;
+        }
+
+        // all controllers see the same state, so none of them learns the moves of the others early
+        let mut directions = Vec::with_capacity(names.len());
+        for (i, controller) in controllers.iter_mut().enumerate() {
+            let direction = if game_state.snakes[i].alive {
+                controller.next_direction(&Observation::new(&game_state, ruleset, i))
+            } else {
+                game_state.snakes[i].direction
+            };
+            directions.push(direction);
+        }
+
+        for (i, (controller, direction)) in controllers.iter().zip(directions).enumerate() {
+            if !game_state.snakes[i].alive {
+                continue;
+            }
+            match controller.get_disqualification() {
+                Some(reason) => {
+                    disqualifications[i] = Some(String::from(reason));
+                    eliminated_at[i] = Some(game_state.tick_count);
+                    game_state.disqualify(i);
+                }
+                None => game_state.snakes[i].direction = direction,
+            }
+        }
+        // a player knocked out before its first move would pause the game for everyone else
+        if game_state.player().direction == Direction::STOP {
+            game_state.player_mut().direction = Direction::UP;
+        }
+
+        game_state.handle_snake_movement(ruleset);
+        for (snake, eliminated) in game_state.snakes.iter().zip(eliminated_at.iter_mut()) {
+            if !snake.alive && eliminated.is_none() {
+                *eliminated = Some(game_state.tick_count);
+            }
+        }
+    }
+
+    // surviving longer is better, snakes eliminated in the same tick or still alive at the end are
+    // ranked by their length
+    let rankings: Vec<(u64, usize)> = game_state.snakes.iter().zip(eliminated_at.iter())
+        .map(|(snake, eliminated)| (eliminated.unwrap_or(u64::MAX), snake.tail_x_pos.len()))
+        .collect();
+    let placements = rankings.iter()
+        .map(|ranking| rankings.iter().filter(|other| *other > ranking).count())
+        .collect();
+
+    MatchResult {
+        placements,
+        disqualifications,
+    }
+}
+
+/// Treats a match as a game between each pair of participants, the change of each rating is the
+/// average of its changes in these games so that matches of different sizes weigh the same
+fn update_ratings(ratings: &mut [Rating], rating_indices: &[usize], placements: &[usize]) {
+    let elos: Vec<f64> = rating_indices.iter().map(|i| ratings[*i].elo).collect();
+    let opponent_count = (rating_indices.len() - 1) as f64;
+
+    for (seat, rating_index) in rating_indices.iter().enumerate() {
+        let mut change = 0.0;
+        for other in (0..rating_indices.len()).filter(|other| *other != seat) {
+            let expected = 1.0 / (1.0 + 10f64.powf((elos[other] - elos[seat]) / 400.0));
+            let actual = match placements[seat].cmp(&placements[other]) {
+                std::cmp::Ordering::Less => 1.0,
+                std::cmp::Ordering::Equal => 0.5,
+                std::cmp::Ordering::Greater => 0.0,
+            };
+            change += actual - expected;
+        }
+
+        let rating = &mut ratings[*rating_index];
+        rating.elo += K_FACTOR * change / opponent_count;
+        rating.matches += 1;
+    }
+}
+
+/// Returns every way to choose k out of n indices in lexicographic order
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut result = Vec::new();
+    let mut group: Vec<usize> = (0..k).collect();
+
+    loop {
+        result.push(group.clone());
+
+        // advance the rightmost index that has not reached its last possible value
+        let advanced = match (0..k).rev().find(|i| group[*i] < n - k + *i) {
+            Some(i) => i,
+            None => return result,
+        };
+        group[advanced] += 1;
+        for i in advanced + 1..k {
+            group[i] = group[i - 1] + 1;
+        }
+    }
+}
+
+fn print_standings(options: &TournamentOptions, ruleset: &Ruleset, standings: &[Standing], ratings: &[Rating], match_count: u64, started: Instant) {
+    println!(
+        "{} tournament of {} controllers ({}), seeds {} to {}, {} matches, took {}",
+        options.format,
+        options.controllers.len(),
+        ruleset.id,
+        options.seed,
+        options.seed.wrapping_add(options.seeds.saturating_sub(1)),
+        match_count,
+        started.elapsed().format_duration(),
+    );
+    println!();
+
+    let mut order: Vec<usize> = (0..standings.len()).collect();
+    order.sort_by(|a, b| ratings[standings[*b].rating_index].elo.total_cmp(&ratings[standings[*a].rating_index].elo));
+
+    println!("{:<6}{:<32}{:>8}{:>9}{:>9}{:>7}{:>7}{:>8}{:>6}", "Rank", "Controller", "Elo", "Change", "Matches", "Wins", "Draws", "Losses", "DQs");
+    for (rank, index) in order.iter().enumerate() {
+        let standing = &standings[*index];
+        let rating = &ratings[standing.rating_index];
+        println!(
+            "{:<6}{:<32}{:>8.1}{:>+9.1}{:>9}{:>7}{:>7}{:>8}{:>6}",
+            rank + 1,
+            rating.controller,
+            rating.elo,
+            rating.elo - standing.initial_elo,
+            standing.wins + standing.draws + standing.losses,
+            standing.wins,
+            standing.draws,
+            standing.losses,
+            standing.disqualifications,
+        );
+    }
+}
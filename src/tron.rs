@@ -0,0 +1,75 @@
+use std::fmt;
+
+use crate::{FIELD_HEIGHT, FIELD_WIDTH};
+use crate::direction::Direction;
+use crate::game_state::{GameState, PLAYER_INDEX};
+use crate::ruleset::Ruleset;
+use crate::snake::Snake;
+
+/// Index of the opponent in versus games
+pub const OPPONENT_INDEX: usize = 1;
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TronOpponent {
+    NONE,
+    LOCAL,
+    BOT,
+}
+
+impl fmt::Display for TronOpponent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl TronOpponent {
+    /// Returns all opponents in menu order
+    pub fn all() -> [TronOpponent; 3] {
+        [TronOpponent::NONE, TronOpponent::LOCAL, TronOpponent::BOT]
+    }
+
+    pub(crate) fn get_description(&self) -> &str {
+        match self {
+            TronOpponent::NONE => "Survive as long as possible",
+            TronOpponent::LOCAL => "Versus a second player steering with I, J, K and L",
+            TronOpponent::BOT => "Versus a computer controlled light cycle",
+        }
+    }
+}
+
+/// Spawns the player in the middle of the field for single player games, in versus games the player
+/// spawns on the left and the opponent on the right heading towards the player. As in every mode the
+/// game starts once the player chooses a direction.
+pub fn spawn_snakes(opponent: TronOpponent) -> Vec<Snake> {
+    if opponent == TronOpponent::NONE {
+        return vec![Snake::new()];
+    }
+
+    let player = Snake::spawn_at(FIELD_WIDTH / 4, FIELD_HEIGHT / 2);
+    let mut opponent_snake = Snake::spawn_at(FIELD_WIDTH * 3 / 4, FIELD_HEIGHT / 2);
+    opponent_snake.direction = Direction::LEFT;
+    opponent_snake.ai_controlled = opponent == TronOpponent::BOT;
+
+    vec![player, opponent_snake]
+}
+
+/// Awards points for each tick the player survives and ends the game once the player crashed or, in
+/// versus games, once at most one light cycle is left
+pub fn handle_tron_tick(game_state: &mut GameState, ruleset: &Ruleset) {
+    // the multiplier is in percent, so the score is calculated from all ticks survived to not round
+    // away the bonus of the mutators on each tick
+    if game_state.player().alive {
+        game_state.current_score = game_state.tick_count * game_state.get_score_multiplier(ruleset) / 100;
+    }
+
+    let opponent_alive = game_state.snakes.get(OPPONENT_INDEX).map(|snake| snake.alive);
+    match opponent_alive {
+        Some(opponent_alive) => {
+            let player_alive = game_state.snakes[PLAYER_INDEX].alive;
+            game_state.won = player_alive && !opponent_alive;
+            game_state.game_over = !player_alive || !opponent_alive;
+        }
+        None => game_state.game_over = !game_state.player().alive,
+    }
+}
@@ -0,0 +1,131 @@
+use std::fs;
+
+use wasmi::{Config, Engine, Instance, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+use wasmi::core::TrapCode;
+
+use crate::controller::{Controller, Observation};
+use crate::direction::Direction;
+
+/// Prefix of controller names running a WebAssembly bot, followed by the path of the module
+pub const WASM_CONTROLLER_PREFIX: &str = "wasm:";
+/// Number of instructions, roughly, a bot may execute each tick
+const FUEL_PER_MOVE: u64 = 10_000_000;
+/// Size in bytes the linear memory of a bot may grow to
+const MEMORY_LIMIT: usize = 16 * 1024 * 1024;
+
+/// Export returning a pointer to a buffer of at least the requested number of bytes
+const ALLOC_EXPORT: &str = "snake_alloc";
+/// Export receiving the pointer and length of the observation and returning the direction
+const NEXT_DIRECTION_EXPORT: &str = "snake_next_direction";
+
+/// The running module of a bot
+struct Sandbox {
+    store: Store<StoreLimits>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    next_direction: TypedFunc<(i32, i32), i32>,
+}
+
+/// Steers the snake with a bot compiled to WebAssembly, run by an embedded interpreter. The module
+/// may not import anything, so all it can do is compute. Each tick the observation is written to its
+/// memory as the same line of JSON external bots receive and the bot answers with 0 for UP, 1 for
+/// DOWN, 2 for LEFT or 3 for RIGHT. A bot that runs out of fuel, grows its memory past the limit,
+/// traps or answers anything else is disqualified.
+pub struct WasmController {
+    path: String,
+    sandbox: Option<Sandbox>,
+    disqualification: Option<String>,
+}
+
+impl WasmController {
+    pub fn new(path: &str) -> Self {
+        WasmController {
+            path: String::from(path),
+            sandbox: None,
+            disqualification: None,
+        }
+    }
+
+    /// Loads the module on the first tick, so that creating the controller to check its name is cheap
+    fn load(&self) -> Result<Sandbox, String> {
+        let wasm = fs::read(&self.path).map_err(|error| format!("could not read '{}': {}", self.path, error))?;
+
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, &wasm).map_err(|error| format!("invalid module: {}", error))?;
+
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(MEMORY_LIMIT)
+            .instances(1)
+            .trap_on_grow_failure(true)
+            .build();
+        let mut store = Store::new(&engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(FUEL_PER_MOVE).expect("fuel is enabled");
+
+        // no host functions are linked, modules importing anything fail to instantiate
+        let instance: Instance = Linker::new(&engine)
+            .instantiate(&mut store, &module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(|error| format!("could not instantiate: {}", error))?;
+
+        let memory = instance.get_memory(&store, "memory").ok_or("module does not export its memory")?;
+        let alloc = instance.get_typed_func(&store, ALLOC_EXPORT)
+            .map_err(|_| format!("module does not export {}(i32) -> i32", ALLOC_EXPORT))?;
+        let next_direction = instance.get_typed_func(&store, NEXT_DIRECTION_EXPORT)
+            .map_err(|_| format!("module does not export {}(i32, i32) -> i32", NEXT_DIRECTION_EXPORT))?;
+
+        Ok(Sandbox { store, memory, alloc, next_direction })
+    }
+
+    fn request_direction(&mut self, observation: &Observation) -> Result<Direction, String> {
+        if self.sandbox.is_none() {
+            self.sandbox = Some(self.load()?);
+        }
+        let sandbox = self.sandbox.as_mut().expect("module has been loaded");
+        let json = observation.to_json();
+
+        sandbox.store.set_fuel(FUEL_PER_MOVE).expect("fuel is enabled");
+        let ptr = sandbox.alloc.call(&mut sandbox.store, json.len() as i32).map_err(describe_trap)?;
+        sandbox.memory.write(&mut sandbox.store, ptr as u32 as usize, json.as_bytes())
+            .map_err(|_| format!("{} returned a buffer outside of the memory", ALLOC_EXPORT))?;
+        let answer = sandbox.next_direction.call(&mut sandbox.store, (ptr, json.len() as i32)).map_err(describe_trap)?;
+
+        match answer {
+            0 => Ok(Direction::UP),
+            1 => Ok(Direction::DOWN),
+            2 => Ok(Direction::LEFT),
+            3 => Ok(Direction::RIGHT),
+            _ => Err(format!("malformed answer {}", answer)),
+        }
+    }
+}
+
+fn describe_trap(error: wasmi::Error) -> String {
+    match error.as_trap_code() {
+        Some(TrapCode::OutOfFuel) => format!("ran out of fuel ({} per move)", FUEL_PER_MOVE),
+        Some(TrapCode::GrowthOperationLimited) => format!("exceeded the memory limit of {} bytes", MEMORY_LIMIT),
+        _ => format!("trapped: {}", error),
+    }
+}
+
+impl Controller for WasmController {
+    fn next_direction(&mut self, observation: &Observation) -> Direction {
+        if self.disqualification.is_some() {
+            return observation.snake().direction;
+        }
+
+        match self.request_direction(observation) {
+            Ok(direction) => direction,
+            Err(reason) => {
+                self.disqualification = Some(reason);
+                observation.snake().direction
+            }
+        }
+    }
+
+    fn get_disqualification(&self) -> Option<&str> {
+        self.disqualification.as_deref()
+    }
+}